@@ -4,7 +4,7 @@ use regex::Regex;
 
 mod parse;
 
-pub use parse::parse;
+pub use parse::parse_with;
 
 /// A pattern match
 ///
@@ -17,6 +17,39 @@ pub struct PatternMatch {
     pub patterns: Vec<Pattern>,
 }
 
+/// A match on the `key=value` pairs of a logfmt line,
+/// a key given alone binds its value to a variable of the same name
+///
+/// ```text
+/// {level: "error", msg, took: /(?P<ms>[0-9]+)ms/}
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct LogfmtMatch {
+    /// The keys which must be present with the pattern their values match
+    pub pairs: Vec<(String, Pattern)>,
+}
+
+/// A match on fixed width columns, each cut from a range of character
+/// positions and trimmed, a range without an end runs to the end of the line
+///
+/// ```text
+/// [0..8 user, 9..15 pid, 65.. cmd]
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct ColumnMatch {
+    /// The columns with the pattern their contents match
+    pub columns: Vec<(Column, Pattern)>,
+}
+
+/// The character positions of a fixed width column, the end is exclusive
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Column {
+    /// The position of the first character of the column
+    pub start: usize,
+    /// The position after the last character, if the column does not run to the end
+    pub end: Option<usize>,
+}
+
 /// A sub pattern of a pattern match
 #[derive(Debug)]
 pub enum Pattern {
@@ -63,12 +96,34 @@ pub enum Match {
     /// ```
     End,
 
-    /// The case to run a statements when a line number is reached
+    /// The case where an input file is started, before its first line
+    ///
+    /// ```text
+    /// beginfile {
+    ///   print("==> ${FILENAME} <==")
+    /// }
+    /// ```
+    BeginFile,
+
+    /// The case where an input file is finished, after its last line
+    ///
+    /// ```text
+    /// endfile {
+    ///   print("${FILENAME}: ${FNR} lines")
+    /// }
+    /// ```
+    EndFile,
+
+    /// The case to run a statements when a line number is reached,
+    /// negative numbers count from the last line which can also be written as `last`
     ///
     /// ```text
     /// 1 {
     ///   print("Begin of input")
     /// }
+    ///
+    /// -2 subst /,$/, ''
+    /// last append ';'
     /// ```
     Index(i64),
 
@@ -86,13 +141,24 @@ pub enum Match {
     ///
     /// This also nests when statements in the body are matches as well.
     Regex(Box<Regex>),
+
+    /// The case to run statements every step lines starting at a line number
+    ///
+    /// ```text
+    /// 0~4 {
+    ///   print("every fourth line")
+    /// }
+    /// ```
+    Step(i64, i64),
 }
 
-/// A range has two matches seperated by a comma
-/// When the first one is matched all of the lines until the end is matched will
+/// A range has a match and an end seperated by a comma
+/// When the first one is matched all of the lines until the end is reached will
 /// execute the body statement.
 ///
-/// Ranges are start inclusive but end exclusive
+/// Ranges are start inclusive but end exclusive, unless the end is
+/// written with `,=` in which case the end line is also in the range
+/// and the captures of the end are added to the scope for that line
 ///
 /// When a start match is a regex and has capture variables it's variables are stored and
 /// supplied for each next until the range ends
@@ -105,8 +171,59 @@ pub enum Match {
 /// }
 /// ```
 ///
+/// A range inside of a block only sees the lines which reach that block,
+/// so a nested range can only start or end within the outer range
+///
+/// The body of a range can have an `enter` and an `exit` block,
+/// which are run once when the range opens and once when it closes
+///
+/// ```text
+/// /^\[(?P<section>\w+)\]/,=/^end/ {
+///   enter { print "<${section}>" }
+///   exit { print "</${section}>" }
+///   print _
+/// }
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct Range {
+    pub start: Match,
+    pub end: RangeEnd,
+
+    /// Whether the end line is part of the range
+    pub inclusive: bool,
+
+    /// Performed when the range opens
+    pub enter: Option<Seq>,
+
+    /// Performed when the range closes or the input ends
+    pub exit: Option<Seq>,
+}
+
+impl Range {
+    /// The enter and exit blocks of the range
+    pub fn hooks(&self) -> impl Iterator<Item = &Seq> {
+        self.enter.iter().chain(self.exit.iter())
+    }
+}
+
+/// How a range finds its end
+///
+/// ```text
+/// /^Header/,/^$/ print _
+/// /^Header/,+5 print _
+/// /^Header/,~4 print _
+/// ```
 #[derive(Debug, PartialEq)]
-pub struct Range(pub Match, pub Match);
+pub enum RangeEnd {
+    /// Ends at a matching line
+    Match(Match),
+
+    /// Ends after the given number of lines following the start
+    Count(i64),
+
+    /// Ends after the next line number which is a multiple of the given number
+    Multiple(i64),
+}
 
 /// A selector is a switch for a guard
 #[derive(Debug, PartialEq)]
@@ -136,6 +253,22 @@ pub enum Selector {
     /// ```
     Pattern(PatternMatch),
 
+    /// A logfmt pattern is given
+    /// ```text
+    /// {level: "error", msg} {
+    ///   print msg
+    /// }
+    /// ```
+    Logfmt(LogfmtMatch),
+
+    /// A fixed width column pattern is given
+    /// ```text
+    /// [0..8 'root', 65.. cmd] {
+    ///   print cmd
+    /// }
+    /// ```
+    Columns(ColumnMatch),
+
     /// A negation of a pattern
     /// ```text
     /// !/pattern/ {
@@ -159,6 +292,47 @@ pub enum Selector {
     /// }
     /// ```
     Disjunction(Box<Selector>, Box<Selector>),
+
+    /// A test for a key in a map
+    /// ```text
+    /// ip in seen {
+    ///   print "${ip} again"
+    /// }
+    /// ```
+    Contains(Expression, String),
+
+    /// A comparison of an expression
+    /// ```text
+    /// [_, _, code] & code >= 500 {
+    ///   print _
+    /// }
+    /// ```
+    Compare(Expression, Comparison),
+}
+
+/// The comparison made against an expression
+///
+/// When both sides are numbers they are compared numerically and when both
+/// are text they are compared as strings, a number is equal to no text
+/// and is neither less nor greater than text
+#[derive(Debug)]
+pub enum Comparison {
+    /// `==`
+    Equal(Expression),
+    /// `!=`
+    NotEqual(Expression),
+    /// `<`
+    Less(Expression),
+    /// `<=`
+    LessEqual(Expression),
+    /// `>`
+    Greater(Expression),
+    /// `>=`
+    GreaterEqual(Expression),
+    /// `~` which also captures named groups
+    Matches(Box<Regex>),
+    /// `!~`
+    NotMatches(Box<Regex>),
 }
 
 /// A expression
@@ -168,6 +342,50 @@ pub enum Expression {
     String(String, bool),
     /// A variable to be resolved
     Identifier(String),
+    /// An integer literal
+    Integer(i64),
+    /// A floating point literal
+    Float(f64),
+    /// The arithmetic negation of an expression
+    /// ```text
+    /// print -offset
+    /// ```
+    Neg(Box<Expression>),
+    /// An entry of a map
+    /// ```text
+    /// print count[ip]
+    /// ```
+    Index(String, Box<Expression>),
+    /// A call to a built-in function
+    /// ```text
+    /// print upper(name)
+    /// ```
+    Call(String, Vec<Expression>),
+    /// An arithmetic operation on two expressions
+    /// ```text
+    /// print (port + 1) * 2
+    /// ```
+    Arithmetic(Operator, Box<Expression>, Box<Expression>),
+    /// A value in the current line when reading JSON lines
+    /// ```text
+    /// .http.status >= 500 print .request.path
+    /// ```
+    Path(Vec<String>),
+}
+
+/// An arithmetic operator
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Operator {
+    /// Addition `+`
+    Add,
+    /// Subtraction `-`
+    Sub,
+    /// Multiplication `*`
+    Mul,
+    /// Division `/`
+    Div,
+    /// Remainder `%`
+    Mod,
 }
 
 /// A statement
@@ -181,6 +399,14 @@ pub enum Statement {
     /// of the instructions left in the program instead of exiting 0
     Quit,
 
+    /// Stops processing the current line,
+    /// continuing the program with the next one
+    ///
+    /// ```text
+    /// /^#/ next
+    /// ```
+    Next,
+
     /// Substitutes the first match in the current line
     /// with the evaluation of an expression
     Subst(Box<Regex>, Expression),
@@ -207,6 +433,157 @@ pub enum Statement {
     /// Binds a global variable
     #[cfg(feature = "bind")]
     Bind(String),
+
+    /// Declares a variable local to the enclosing block
+    ///
+    /// ```text
+    /// /host: (?P<host>.*)/ {
+    ///   let name = host
+    /// }
+    /// ```
+    Let(String, Expression),
+
+    /// Assigns to the closest variable with the given name,
+    /// if there is none a global variable is created which
+    /// persists across lines
+    ///
+    /// ```text
+    /// /host: (?P<host>.*)/ last = host
+    /// ```
+    Assign(Target, Expression),
+
+    /// Accumulates the value of an expression into a global variable
+    ///
+    /// ```text
+    /// /ERROR/ incr errors
+    /// /bytes=(?P<n>[0-9]+)/ sum total, n
+    /// /user=(?P<user>[a-z]+)/ push users, user
+    /// $ print "${errors} errors, ${total} bytes for ${users}"
+    /// ```
+    Accumulate(Accumulator, Target, Expression),
+
+    /// Removes an entry from a map
+    ///
+    /// ```text
+    /// /close (?P<id>[0-9]+)/ delete open[id]
+    /// ```
+    DeleteEntry(String, Expression),
+
+    /// Drops the current line from the implicit print,
+    /// the rest of the program still runs for the line
+    ///
+    /// ```text
+    /// /^DEBUG/ delete
+    /// ```
+    Delete,
+
+    /// Moves text between the current line and the hold space
+    ///
+    /// ```text
+    /// /^BEGIN/ hold
+    /// !/^BEGIN/ hold_append
+    /// /^END/ { fetch; print _ }
+    /// ```
+    Hold(HoldSpace),
+
+    /// Appends the next input line to the current line seperated by a newline,
+    /// the line is unchanged when there is no more input
+    ///
+    /// ```text
+    /// /,$/ { pull; gsubst /\n/, ' ' }
+    /// ```
+    Pull,
+
+    /// Runs a sequence for each key in a map
+    ///
+    /// ```text
+    /// $ {
+    ///   for ip in count {
+    ///     print "${ip}: ${count[ip]}"
+    ///   }
+    /// }
+    /// ```
+    For(String, String, Seq),
+
+    /// Calls a user defined procedure with the given arguments
+    ///
+    /// ```text
+    /// show('size', size)
+    /// ```
+    Call(String, Vec<Expression>),
+
+    /// Writes variables as an escaped record, either the named ones
+    /// or every variable of the current scopes sorted by name.
+    /// The first csv row is preceded by a header of the names
+    ///
+    /// ```text
+    /// /(?P<user>[a-z]+) (?P<status>[0-9]+)/ emit csv(user, status)
+    /// [host, path] emit json
+    /// ```
+    Emit(Format, Option<Vec<String>>),
+}
+
+/// Read-only variables describing the input, along side `_`
+///
+/// - `NR` the number of the current line
+/// - `FNR` the number of the current line within the current file
+/// - `NF` the number of fields of the current line per the seperator
+/// - `FILENAME` the name of the current input file
+/// - `OFFSET` the byte offset of the current line within the current file
+///
+/// ```text
+/// NF > 3 print "${FILENAME}:${FNR}: ${_}"
+/// ```
+pub const BUILTINS: &[&str] = &["NR", "FNR", "NF", "FILENAME", "OFFSET"];
+
+/// A variable or map entry which is assigned to
+#[derive(Debug, PartialEq)]
+pub enum Target {
+    /// A plain variable
+    Variable(String),
+
+    /// The entry of a map
+    Entry(String, Expression),
+}
+
+/// The strategy used to accumulate a value
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Accumulator {
+    /// Adds to a number starting from 0,
+    /// `incr name` is the same as `sum name, 1`
+    Sum,
+    /// Keeps the smallest number seen
+    Min,
+    /// Keeps the largest number seen
+    Max,
+    /// Appends to a list
+    Push,
+}
+
+/// The format of a record written by emit
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    /// A JSON object with a key for each variable
+    Json,
+    /// A row of comma seperated values quoted per RFC 4180
+    Csv,
+    /// Space seperated `key=value` pairs, quoting values as needed
+    Logfmt,
+}
+
+/// An operation on the hold space, a buffer kept between lines
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HoldSpace {
+    /// Replaces the hold space with the line (sed `h`)
+    Hold,
+    /// Appends a newline and the line to the hold space (sed `H`)
+    HoldAppend,
+    /// Replaces the line with the hold space (sed `g`)
+    Fetch,
+    /// Appends a newline and the hold space to the line (sed `G`)
+    FetchAppend,
+    /// Exchanges the line and the hold space (sed `x`)
+    Swap,
 }
 
 /// A guarded statement or a plain one
@@ -220,6 +597,60 @@ pub enum Body {
 
     /// A sequence of statement which is executed dependent on the selector
     Guard(Selector, Seq),
+
+    /// A sequence of statements executed when the selector matches
+    /// and an alternative sequence executed when it does not
+    ///
+    /// ```text
+    /// /ERROR (?P<msg>.*)/ {
+    ///   print msg
+    /// } else /WARN/ {
+    ///   print 'warning'
+    /// } else {
+    ///   print 'ok'
+    /// }
+    /// ```
+    Branch(Selector, Seq, Seq),
+
+    /// Arms where only the first matching arm is executed,
+    /// with an optional default for when no arms match
+    ///
+    /// ```text
+    /// match {
+    ///   /ERROR (?P<msg>.*)/ => print msg,
+    ///   [_, level] => { print level },
+    ///   _ => print 'unknown',
+    /// }
+    /// ```
+    Switch(Vec<Arm>, Option<Seq>),
+
+    /// A procedure definition, which is only allowed at the top level
+    Def(Procedure),
+}
+
+/// An arm of a match with its selector and body
+#[derive(Debug, PartialEq)]
+pub struct Arm(pub Selector, pub Seq);
+
+/// A user defined procedure
+///
+/// ```text
+/// def show(label, value) {
+///   print "${label}: ${value}"
+/// }
+///
+/// [name, size] show(name, size)
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct Procedure {
+    /// The name the procedure is called by
+    pub name: String,
+
+    /// The parameters bound to the arguments of a call
+    pub params: Vec<String>,
+
+    /// The statements performed when called
+    pub body: Seq,
 }
 
 /// Contains multiple sub nodes
@@ -230,6 +661,16 @@ pub struct Seq {
     pub(crate) toplevel: bool,
 }
 
+impl Target {
+    /// The name of the variable or map being assigned
+    pub fn name(&self) -> &str {
+        match self {
+            Target::Variable(name) => name,
+            Target::Entry(name, _) => name,
+        }
+    }
+}
+
 impl PartialEq for Match {
     fn eq(&self, other: &Match) -> bool {
         match (self, other) {
@@ -237,6 +678,27 @@ impl PartialEq for Match {
             (Match::Regex(a), Match::Regex(b)) => a.to_string() == b.to_string(),
             (Match::Begin, Match::Begin) => true,
             (Match::End, Match::End) => true,
+            (Match::BeginFile, Match::BeginFile) => true,
+            (Match::EndFile, Match::EndFile) => true,
+            (Match::Step(sf, ss), Match::Step(of, os)) => sf == of && ss == os,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for Comparison {
+    fn eq(&self, other: &Comparison) -> bool {
+        use Comparison::*;
+
+        match (self, other) {
+            (Equal(a), Equal(b)) => a == b,
+            (NotEqual(a), NotEqual(b)) => a == b,
+            (Less(a), Less(b)) => a == b,
+            (LessEqual(a), LessEqual(b)) => a == b,
+            (Greater(a), Greater(b)) => a == b,
+            (GreaterEqual(a), GreaterEqual(b)) => a == b,
+            (Matches(a), Matches(b)) => a.to_string() == b.to_string(),
+            (NotMatches(a), NotMatches(b)) => a.to_string() == b.to_string(),
             _ => false,
         }
     }
@@ -258,6 +720,10 @@ impl PartialEq for Statement {
     fn eq(&self, other: &Statement) -> bool {
         match (self, other) {
             (Statement::Quit, Statement::Quit) => true,
+            (Statement::Next, Statement::Next) => true,
+            (Statement::Delete, Statement::Delete) => true,
+            (Statement::Hold(so), Statement::Hold(oo)) => so == oo,
+            (Statement::Pull, Statement::Pull) => true,
             (Statement::Print(se), Statement::Print(oe)) => se == oe,
             (Statement::Subst(sr, se), Statement::Subst(or, oe)) => {
                 sr.to_string() == or.to_string() && se == oe
@@ -270,6 +736,19 @@ impl PartialEq for Statement {
             (Statement::Exec(se), Statement::Exec(oe)) => se == oe,
             (Statement::Append(se), Statement::Append(oe)) => se == oe,
             (Statement::Set(se), Statement::Set(oe)) => se == oe,
+            (Statement::Let(sn, se), Statement::Let(on, oe)) => sn == on && se == oe,
+            (Statement::Assign(sn, se), Statement::Assign(on, oe)) => sn == on && se == oe,
+            (Statement::Accumulate(sa, sn, se), Statement::Accumulate(oa, on, oe)) => {
+                sa == oa && sn == on && se == oe
+            }
+            (Statement::DeleteEntry(sn, se), Statement::DeleteEntry(on, oe)) => {
+                sn == on && se == oe
+            }
+            (Statement::For(sv, sm, ss), Statement::For(ov, om, os)) => {
+                sv == ov && sm == om && ss == os
+            }
+            (Statement::Call(sn, sa), Statement::Call(on, oa)) => sn == on && sa == oa,
+            (Statement::Emit(sf, sn), Statement::Emit(of, on)) => sf == of && sn == on,
             _ => false,
         }
     }
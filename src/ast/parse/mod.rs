@@ -3,12 +3,28 @@ mod utils;
 
 use super::*;
 use crate::lex::Token;
+use crate::runtime::RangeCap;
 use utils::*;
 
 /// Parses a romulus token stream and creates a romulus AST,
 /// or returns an error message
+#[cfg(test)]
 pub fn parse(tokens: Vec<Token>) -> Result<Seq, String> {
-    let (node, offset) = Seq::parse_toplevel(&tokens, 0)?;
+    parse_with(tokens, &mut |path| {
+        Err(format!(
+            "unable to include {}, no file loader is given",
+            path
+        ))
+    })
+}
+
+/// Parses a romulus token stream where each top level `include "path"`
+/// is replaced by the top level bodies loaded by `include`
+pub fn parse_with(
+    tokens: Vec<Token>,
+    include: &mut dyn FnMut(&str) -> Result<Seq, String>,
+) -> Result<Seq, String> {
+    let (node, offset) = Seq::parse_toplevel(&tokens, 0, include)?;
 
     if offset != tokens.len() {
         return Err("Did not consume the whole program".to_string());
@@ -27,7 +43,7 @@ macro_rules! guard_eof {
     };
 }
 
-pub(self) trait Parsable: Sized {
+trait Parsable: Sized {
     fn parse(tokens: &[Token], pos: usize) -> Result<(Self, usize), String>;
 
     fn parse_mut(tokens: &[Token], pos: &mut usize) -> Result<Self, String> {
@@ -45,7 +61,7 @@ impl Parsable for Seq {
         let toplevel = false;
 
         while pos != tokens.len() {
-            subnodes.push(Body::parse_mut(&tokens, &mut pos)?);
+            subnodes.push(Body::parse_mut(tokens, &mut pos)?);
         }
 
         Ok((Seq { subnodes, toplevel }, pos))
@@ -53,13 +69,42 @@ impl Parsable for Seq {
 }
 
 impl Seq {
-    fn parse_toplevel(tokens: &[Token], pos: usize) -> Result<(Seq, usize), String> {
+    fn parse_toplevel(
+        tokens: &[Token],
+        pos: usize,
+        include: &mut dyn FnMut(&str) -> Result<Seq, String>,
+    ) -> Result<(Seq, usize), String> {
         let mut pos = pos;
         let mut subnodes = Vec::new();
         let toplevel = true;
 
         while pos != tokens.len() {
-            subnodes.push(Body::parse_mut(&tokens, &mut pos)?);
+            match (tokens.get(pos), tokens.get(pos + 1)) {
+                (Some(Token::Identifier(id)), Some(Token::String(path, _))) if id == "include" => {
+                    subnodes.extend(include(path)?.subnodes);
+                    pos += 2;
+                }
+
+                (Some(Token::Identifier(id)), _) if id == "def" => {
+                    subnodes.push(Body::Def(Procedure::parse_mut(tokens, &mut pos)?));
+                }
+
+                _ => subnodes.push(Body::parse_mut(tokens, &mut pos)?),
+            }
+        }
+
+        let mut names = Vec::new();
+        for sub in &subnodes {
+            if let Body::Def(procedure) = sub {
+                if names.contains(&&procedure.name) {
+                    return Err(format!(
+                        "procedure {} is defined more than once",
+                        procedure.name
+                    ));
+                }
+
+                names.push(&procedure.name);
+            }
         }
 
         Ok((Seq { subnodes, toplevel }, pos))
@@ -69,10 +114,24 @@ impl Seq {
 impl Parsable for Body {
     fn parse(tokens: &[Token], pos: usize) -> Result<(Body, usize), String> {
         let mut pos = pos;
-        let sel = match Selector::parse_mut(&tokens, &mut pos) {
+
+        if tokens.get(pos) == Some(&Token::Identifier("match".to_string()))
+            && tokens.get(pos + 1) == Some(&Token::Paren('{'))
+        {
+            return parse_switch(tokens, pos + 2);
+        }
+
+        // an assignment is never a selector, `last = _` is not the last line
+        if tokens.get(pos + 1) == Some(&Token::Symbol('=')) {
+            let (node, next) = Statement::parse(tokens, pos)?;
+            return Ok((Body::Bare(node), next));
+        }
+        let sel = match Selector::parse_mut(tokens, &mut pos) {
             Ok(sel) => sel,
+            // no statement starts like fixed width columns
+            Err(err) if is_columns(tokens, pos) => return Err(err),
             Err(_) => {
-                let (node, next) = Statement::parse(&tokens, pos)?;
+                let (node, next) = Statement::parse(tokens, pos)?;
                 return Ok((Body::Bare(node), next));
             }
         };
@@ -80,22 +139,222 @@ impl Parsable for Body {
         if Some(&Token::Paren('{')) != tokens.get(pos) {
             let statement = Statement::parse_mut(tokens, &mut pos)?;
 
-            return Ok((Body::Single(sel, statement), pos));
+            if !is_else(tokens, pos) {
+                return Ok((Body::Single(sel, statement), pos));
+            }
+
+            let then = Seq {
+                subnodes: vec![Body::Bare(statement)],
+                toplevel: false,
+            };
+
+            let otherwise = parse_else(tokens, &mut pos)?;
+
+            return Ok((Body::Branch(sel, then, otherwise), pos));
         }
 
         pos += 1;
 
-        Ok((
-            Body::Guard(
+        let mut sel = sel;
+        let seq = match sel {
+            Selector::Range(ref mut range) => parse_range_body(range, tokens, &mut pos)?,
+            _ => Seq {
+                subnodes: parse_until(Token::Paren('}'), tokens, &mut pos)?,
+                toplevel: false,
+            },
+        };
+
+        if !is_else(tokens, pos) {
+            return Ok((Body::Guard(sel, seq), pos));
+        }
+
+        let otherwise = parse_else(tokens, &mut pos)?;
+
+        Ok((Body::Branch(sel, seq, otherwise), pos))
+    }
+}
+
+impl Parsable for Procedure {
+    fn parse(tokens: &[Token], pos: usize) -> Result<(Procedure, usize), String> {
+        let mut pos = pos;
+
+        expect_token(Token::Identifier("def".to_string()), tokens, &mut pos)?;
+        let (name, mut pos) = parse_id(tokens, pos)?;
+        expect_token(Token::Paren('('), tokens, &mut pos)?;
+
+        let mut params = Vec::new();
+        while tokens.get(pos) != Some(&Token::Paren(')')) {
+            if !params.is_empty() {
+                expect_token(Token::Comma, tokens, &mut pos)?;
+            }
+
+            let (param, p) = parse_id(tokens, pos)?;
+            if params.contains(&param) {
+                return Err(format!(
+                    "parameter {} of {} is given more than once",
+                    param, name
+                ));
+            }
+
+            params.push(param);
+            pos = p;
+        }
+
+        pos += 1;
+        expect_token(Token::Paren('{'), tokens, &mut pos)?;
+
+        let body = Seq {
+            subnodes: parse_until(Token::Paren('}'), tokens, &mut pos)?,
+            toplevel: false,
+        };
+
+        if body.num_ranges() != 0 {
+            return Err("ranges are not allowed inside of a procedure".to_string());
+        }
+
+        Ok((Procedure { name, params, body }, pos))
+    }
+}
+
+/// Parses the arms of a match after the opening brace
+fn parse_switch(tokens: &[Token], pos: usize) -> Result<(Body, usize), String> {
+    let mut pos = pos;
+    let mut arms = Vec::new();
+    let mut default = None;
+
+    while tokens.get(pos) != Some(&Token::Paren('}')) {
+        if default.is_some() {
+            return Err("the default arm of a match must be the last arm".to_string());
+        }
+
+        let sel = if tokens.get(pos) == Some(&Token::Identifier("_".to_string())) {
+            pos += 1;
+            None
+        } else {
+            Some(Selector::parse_mut(tokens, &mut pos)?)
+        };
+
+        expect_token(Token::Arrow, tokens, &mut pos)?;
+
+        let seq = if tokens.get(pos) == Some(&Token::Paren('{')) {
+            pos += 1;
+            Seq {
+                subnodes: parse_until(Token::Paren('}'), tokens, &mut pos)?,
+                toplevel: false,
+            }
+        } else {
+            Seq {
+                subnodes: vec![Body::Bare(Statement::parse_mut(tokens, &mut pos)?)],
+                toplevel: false,
+            }
+        };
+
+        match sel {
+            Some(sel) => arms.push(Arm(sel, seq)),
+            None => default = Some(seq),
+        }
+
+        if tokens.get(pos) == Some(&Token::Comma) {
+            pos += 1;
+        }
+    }
+
+    // begin and end arms only run for their events, never for a line
+    let lifecycle = arms
+        .iter()
+        .filter(|Arm(sel, _)| {
+            matches!(
                 sel,
-                Seq {
-                    subnodes: parse_until(Token::Paren('}'), tokens, &mut pos)?,
-                    toplevel: false,
-                },
-            ),
-            pos,
-        ))
+                Selector::Match(Match::Begin | Match::End | Match::BeginFile | Match::EndFile)
+            )
+        })
+        .count();
+
+    if lifecycle > 0 && (lifecycle < arms.len() || default.is_some()) {
+        return Err("a match can not mix begin and end arms with line arms".to_string());
+    }
+
+    Ok((Body::Switch(arms, default), pos + 1))
+}
+
+/// Parses the block of a range, where `enter { ... }` and `exit { ... }`
+/// are hooks for when the range opens and closes rather than part of the body
+fn parse_range_body(range: &mut Range, tokens: &[Token], pos: &mut usize) -> Result<Seq, String> {
+    let mut subnodes = Vec::new();
+
+    while tokens.get(*pos) != Some(&Token::Paren('}')) {
+        let hook = match (guard_eof!(tokens.get(*pos)), tokens.get(*pos + 1)) {
+            (Token::Identifier(id), Some(Token::Paren('{'))) if id == "enter" || id == "exit" => {
+                id.as_str()
+            }
+            _ => {
+                subnodes.push(Body::parse_mut(tokens, pos)?);
+                continue;
+            }
+        };
+
+        *pos += 2;
+        let block = Seq {
+            subnodes: parse_until(Token::Paren('}'), tokens, pos)?,
+            toplevel: false,
+        };
+
+        if block.num_ranges() != 0 {
+            return Err(format!(
+                "ranges are not allowed inside of an {} block",
+                hook
+            ));
+        }
+
+        let slot = if hook == "enter" {
+            &mut range.enter
+        } else {
+            &mut range.exit
+        };
+
+        if slot.is_some() {
+            return Err(format!("a range can only have one {} block", hook));
+        }
+
+        *slot = Some(block);
     }
+
+    *pos += 1;
+
+    Ok(Seq {
+        subnodes,
+        toplevel: false,
+    })
+}
+
+fn is_else(tokens: &[Token], pos: usize) -> bool {
+    tokens.get(pos) == Some(&Token::Identifier("else".to_string()))
+}
+
+fn is_columns(tokens: &[Token], pos: usize) -> bool {
+    tokens.get(pos) == Some(&Token::Paren('['))
+        && match column_bound(tokens, pos + 1) {
+            Some((_, next)) => tokens.get(next) == Some(&Token::Dots),
+            None => false,
+        }
+}
+
+/// Parses the body after an else, either a block or a
+/// single (possibly guarded) body for else chains
+fn parse_else(tokens: &[Token], pos: &mut usize) -> Result<Seq, String> {
+    *pos += 1;
+
+    let subnodes = if tokens.get(*pos) == Some(&Token::Paren('{')) {
+        *pos += 1;
+        parse_until(Token::Paren('}'), tokens, pos)?
+    } else {
+        vec![Body::parse_mut(tokens, pos)?]
+    };
+
+    Ok(Seq {
+        subnodes,
+        toplevel: false,
+    })
 }
 
 impl Parsable for Selector {
@@ -144,11 +403,21 @@ impl Selector {
     fn parse_single(tokens: &[Token], pos: usize) -> Result<(Self, usize), String> {
         let mut pos = pos;
         match tokens.get(pos) {
+            Some(&Token::Paren('[')) if is_columns(tokens, pos) => Ok((
+                Selector::Columns(ColumnMatch::parse_mut(tokens, &mut pos)?),
+                pos,
+            )),
+
             Some(&Token::Paren('[')) => Ok((
                 Selector::Pattern(PatternMatch::parse_mut(tokens, &mut pos)?),
                 pos,
             )),
 
+            Some(&Token::Paren('{')) => Ok((
+                Selector::Logfmt(LogfmtMatch::parse_mut(tokens, &mut pos)?),
+                pos,
+            )),
+
             Some(&Token::Paren('(')) => {
                 pos += 1;
                 let sel = Selector::parse_mut(tokens, &mut pos)?;
@@ -165,22 +434,87 @@ impl Selector {
                 Ok((sel, pos))
             }
             _ => {
-                let s = Match::parse_mut(tokens, &mut pos)?;
+                let step = matches!(
+                    (tokens.get(pos), tokens.get(pos + 1), tokens.get(pos + 2)),
+                    (
+                        Some(Token::Number(_)),
+                        Some(Token::Comparison("~")),
+                        Some(Token::Number(_))
+                    )
+                );
+
+                if let (false, Ok((expr, next))) = (step, Expression::parse(tokens, pos)) {
+                    if tokens.get(next) == Some(&Token::Identifier("in".to_string())) {
+                        let (map, end) = parse_id(tokens, next + 1)?;
+
+                        return Ok((Selector::Contains(expr, map), end));
+                    }
+
+                    if let Some(Token::Comparison(_)) = tokens.get(next) {
+                        let (comparison, end) = Comparison::parse(tokens, next)?;
 
-                if Some(&Token::Comma) != tokens.get(pos) {
-                    return Ok((Selector::Match(s), pos));
+                        return Ok((Selector::Compare(expr, comparison), end));
+                    }
                 }
 
-                pos += 1;
+                let (s, next) = Match::parse(tokens, pos)?;
 
-                let e = Match::parse_mut(tokens, &mut pos)?;
+                if Some(&Token::Comma) != tokens.get(next) {
+                    return Ok((Selector::Match(s), next));
+                }
 
-                Ok((Selector::Range(Range(s, e)), pos))
+                let range = Range::parse_mut(tokens, &mut pos)?;
+
+                Ok((Selector::Range(range), pos))
             }
         }
     }
 }
 
+impl Parsable for Comparison {
+    fn parse(tokens: &[Token], pos: usize) -> Result<(Comparison, usize), String> {
+        let op = match guard_eof!(tokens.get(pos)) {
+            Token::Comparison(op) => *op,
+            token => return Err(format!("expected a comparison but received {:?}", token)),
+        };
+
+        if op == "~" || op == "!~" {
+            let regex = match tokens.get(pos + 1) {
+                Some(Token::Regex(pat, flags)) => {
+                    regex::to_regex(pat.to_string(), flags.to_string())?
+                }
+                token => {
+                    return Err(format!(
+                        "expected a regex for {} but received {:?}",
+                        op, token
+                    ))
+                }
+            };
+
+            let comparison = if op == "~" {
+                Comparison::Matches(regex)
+            } else {
+                Comparison::NotMatches(regex)
+            };
+
+            return Ok((comparison, pos + 2));
+        }
+
+        let (expr, end) = Expression::parse(tokens, pos + 1)?;
+
+        let comparison = match op {
+            "==" => Comparison::Equal(expr),
+            "!=" => Comparison::NotEqual(expr),
+            "<" => Comparison::Less(expr),
+            "<=" => Comparison::LessEqual(expr),
+            ">" => Comparison::Greater(expr),
+            _ => Comparison::GreaterEqual(expr),
+        };
+
+        Ok((comparison, end))
+    }
+}
+
 impl Parsable for PatternMatch {
     fn parse(tokens: &[Token], pos: usize) -> Result<(PatternMatch, usize), String> {
         let token = guard_eof!(tokens.get(pos));
@@ -200,7 +534,7 @@ impl Parsable for PatternMatch {
         let mut cur = pos + 1;
 
         loop {
-            patterns.push(Pattern::parse_mut(&tokens, &mut cur)?);
+            patterns.push(Pattern::parse_mut(tokens, &mut cur)?);
 
             if Some(&Token::Paren(']')) == tokens.get(cur) {
                 break;
@@ -217,10 +551,128 @@ impl Parsable for PatternMatch {
     }
 }
 
+impl Parsable for ColumnMatch {
+    fn parse(tokens: &[Token], pos: usize) -> Result<(ColumnMatch, usize), String> {
+        let mut cur = pos;
+        expect_token(Token::Paren('['), tokens, &mut cur)?;
+
+        let mut columns = Vec::new();
+
+        loop {
+            let column = Column::parse_mut(tokens, &mut cur)?;
+            let pattern = Pattern::parse_mut(tokens, &mut cur)?;
+
+            columns.push((column, pattern));
+
+            if Some(&Token::Paren(']')) == tokens.get(cur) {
+                break;
+            }
+
+            if Some(&Token::Comma) != tokens.get(cur) {
+                return Err(format!("expected comma but received {:?}", tokens.get(cur)));
+            }
+
+            cur += 1;
+        }
+
+        Ok((ColumnMatch { columns }, cur + 1))
+    }
+}
+
+impl Parsable for Column {
+    fn parse(tokens: &[Token], pos: usize) -> Result<(Column, usize), String> {
+        let (start, mut cur) = match column_bound(tokens, pos) {
+            Some((start, next)) if start >= 0 => (start as usize, next),
+            Some((start, _)) => {
+                return Err(format!("the column {}.. starts before the line", start))
+            }
+            None => {
+                return Err(format!(
+                    "expected the start of a column but received {:?}",
+                    guard_eof!(tokens.get(pos))
+                ))
+            }
+        };
+
+        expect_token(Token::Dots, tokens, &mut cur)?;
+
+        let end = match column_bound(tokens, cur) {
+            Some((end, _)) if end < 0 => {
+                return Err(format!(
+                    "the column {}..{} ends before the line",
+                    start, end
+                ))
+            }
+            Some((end, _)) if end as usize <= start => {
+                return Err(format!("the column {}..{} is empty", start, end))
+            }
+            Some((end, next)) => {
+                cur = next;
+                Some(end as usize)
+            }
+            None => None,
+        };
+
+        Ok((Column { start, end }, cur))
+    }
+}
+
+/// Reads a possibly negative number bounding a column,
+/// a negative number is only lexed as one token at the start of a line
+fn column_bound(tokens: &[Token], pos: usize) -> Option<(i64, usize)> {
+    match (tokens.get(pos), tokens.get(pos + 1)) {
+        (Some(Token::Number(num)), _) => Some((*num, pos + 1)),
+        (Some(Token::Symbol('-')), Some(Token::Number(num))) => Some((-num, pos + 2)),
+        _ => None,
+    }
+}
+
+impl Parsable for LogfmtMatch {
+    fn parse(tokens: &[Token], pos: usize) -> Result<(LogfmtMatch, usize), String> {
+        let mut cur = pos;
+        expect_token(Token::Paren('{'), tokens, &mut cur)?;
+
+        let mut pairs = Vec::new();
+
+        loop {
+            // keys which are not identifiers such as `trace-id` are quoted
+            let (key, quoted) = match guard_eof!(tokens.get(cur)) {
+                Token::Identifier(key) => (key.to_string(), false),
+                Token::String(key, _) => (key.to_string(), true),
+                token => return Err(format!("expected a key but received {:?}", token)),
+            };
+            cur += 1;
+
+            let pattern = match tokens.get(cur) {
+                Some(Token::Symbol(':')) => {
+                    cur += 1;
+                    Pattern::parse_mut(tokens, &mut cur)?
+                }
+                _ if quoted => return Err(format!("expected a pattern for the key {}", key)),
+                _ => Pattern::Identifier(key.clone()),
+            };
+
+            pairs.push((key, pattern));
+
+            if Some(&Token::Paren('}')) == tokens.get(cur) {
+                break;
+            }
+
+            if Some(&Token::Comma) != tokens.get(cur) {
+                return Err(format!("expected comma but received {:?}", tokens.get(cur)));
+            }
+
+            cur += 1;
+        }
+
+        Ok((LogfmtMatch { pairs }, cur + 1))
+    }
+}
+
 impl Parsable for Pattern {
     fn parse(tokens: &[Token], pos: usize) -> Result<(Pattern, usize), String> {
         match tokens.get(pos) {
-            Some(&Token::Regex(ref pattern, ref flags)) => {
+            Some(Token::Regex(pattern, flags)) => {
                 let regex = regex::to_regex(pattern.to_string(), flags.to_string())?;
                 Ok((Pattern::Regex(regex), pos + 1))
             }
@@ -230,9 +682,7 @@ impl Parsable for Pattern {
                 pos + 1,
             )),
 
-            Some(&Token::Identifier(ref name)) => {
-                Ok((Pattern::Identifier(name.to_string()), pos + 1))
-            }
+            Some(Token::Identifier(name)) => Ok((Pattern::Identifier(name.to_string()), pos + 1)),
 
             None => Err(String::from("unexpected EOF")),
 
@@ -249,7 +699,20 @@ impl Parsable for Match {
         let token = guard_eof!(tokens.get(pos));
 
         match token {
+            Token::Number(first) if tokens.get(pos + 1) == Some(&Token::Comparison("~")) => {
+                match tokens.get(pos + 2) {
+                    Some(Token::Number(step)) => Ok((Match::Step(*first, *step), pos + 3)),
+                    token => Err(format!("expected a step but received {:?}", token)),
+                }
+            }
             Token::Number(num) => Ok((Match::Index(*num), pos + 1)),
+            Token::Symbol('-') => match tokens.get(pos + 1) {
+                Some(Token::Number(num)) => Ok((Match::Index(-num), pos + 2)),
+                token => Err(format!("expected a line number but received {:?}", token)),
+            },
+            Token::Identifier(id) if id == "last" => Ok((Match::Index(-1), pos + 1)),
+            Token::Identifier(id) if id == "beginfile" => Ok((Match::BeginFile, pos + 1)),
+            Token::Identifier(id) if id == "endfile" => Ok((Match::EndFile, pos + 1)),
             Token::Regex(pattern, flags) => {
                 let regex = regex::to_regex(pattern.to_string(), flags.to_string())?;
                 Ok((Match::Regex(regex), pos + 1))
@@ -268,7 +731,7 @@ impl Parsable for Match {
 impl Parsable for Range {
     fn parse(tokens: &[Token], pos: usize) -> Result<(Range, usize), String> {
         let mut pos = pos;
-        let start_match = Match::parse_mut(&tokens, &mut pos)?;
+        let start_match = Match::parse_mut(tokens, &mut pos)?;
 
         if Some(&Token::Comma) != tokens.get(pos) {
             return Err(format!(
@@ -279,9 +742,45 @@ impl Parsable for Range {
 
         pos += 1;
 
-        let end_match = Match::parse_mut(&tokens, &mut pos)?;
+        let inclusive = tokens.get(pos) == Some(&Token::Symbol('='));
+        if inclusive {
+            pos += 1;
+        }
 
-        Ok((Range(start_match, end_match), pos))
+        let end = RangeEnd::parse_mut(tokens, &mut pos)?;
+
+        Ok((
+            Range {
+                start: start_match,
+                end,
+                inclusive,
+                enter: None,
+                exit: None,
+            },
+            pos,
+        ))
+    }
+}
+
+impl Parsable for RangeEnd {
+    fn parse(tokens: &[Token], pos: usize) -> Result<(RangeEnd, usize), String> {
+        match (guard_eof!(tokens.get(pos)), tokens.get(pos + 1)) {
+            (Token::Symbol('+'), Some(Token::Number(count))) => {
+                Ok((RangeEnd::Count(*count), pos + 2))
+            }
+            (Token::Comparison("~"), Some(Token::Number(multiple))) => {
+                Ok((RangeEnd::Multiple(*multiple), pos + 2))
+            }
+            (Token::Symbol('+'), token) | (Token::Comparison("~"), token) => Err(format!(
+                "expected a number of lines but received {:?}",
+                token
+            )),
+
+            _ => {
+                let (end, p) = Match::parse(tokens, pos)?;
+                Ok((RangeEnd::Match(end), p))
+            }
+        }
     }
 }
 
@@ -289,12 +788,19 @@ impl Parsable for Statement {
     fn parse(tokens: &[Token], pos: usize) -> Result<(Statement, usize), String> {
         let token = guard_eof!(tokens.get(pos));
 
+        if matches!(token, Token::Index(_)) || tokens.get(pos + 1) == Some(&Token::Symbol('=')) {
+            let (target, expr, p) = parse_assignment(tokens, pos)?;
+            return Ok((Statement::Assign(target, expr), p));
+        }
+
         let id = match token {
             Token::Identifier(id) => id,
             _ => return Err(format!("expected identifier but received {:?}", token)),
         };
 
-        let parens = tokens.get(pos + 1) == Some(&Token::Paren('('));
+        // statements taking a single expression leave parens to the expression
+        // so that `print (a + b) / 2` is not read as `print(a + b)`
+        let parens = tokens.get(pos + 1) == Some(&Token::Paren('(')) && !takes_expression(id);
         let param_pos = if parens { pos + 2 } else { pos + 1 };
 
         let (statement, end_pos) = match &id[..] {
@@ -303,6 +809,20 @@ impl Parsable for Statement {
                 (Statement::Print(expr), p)
             }
             "quit" => (Statement::Quit, param_pos),
+            "next" => (Statement::Next, param_pos),
+            "pull" => (Statement::Pull, param_pos),
+
+            "hold" | "hold_append" | "fetch" | "fetch_append" | "swap" => {
+                let op = match &id[..] {
+                    "hold" => HoldSpace::Hold,
+                    "hold_append" => HoldSpace::HoldAppend,
+                    "fetch" => HoldSpace::Fetch,
+                    "fetch_append" => HoldSpace::FetchAppend,
+                    _ => HoldSpace::Swap,
+                };
+
+                (Statement::Hold(op), param_pos)
+            }
 
             "subst" => {
                 let regex = match tokens.get(param_pos) {
@@ -379,12 +899,133 @@ impl Parsable for Statement {
                 (Statement::Set(expr), p)
             }
 
+            "incr" => {
+                let (target, p) = Target::parse(tokens, param_pos)?;
+                (
+                    Statement::Accumulate(Accumulator::Sum, target, Expression::Integer(1)),
+                    p,
+                )
+            }
+
+            "sum" | "min" | "max" | "push" => {
+                let acc = match &id[..] {
+                    "sum" => Accumulator::Sum,
+                    "min" => Accumulator::Min,
+                    "max" => Accumulator::Max,
+                    _ => Accumulator::Push,
+                };
+
+                let (target, mut p) = Target::parse(tokens, param_pos)?;
+                expect_token(Token::Comma, tokens, &mut p)?;
+                let expr = Expression::parse_mut(tokens, &mut p)?;
+
+                (Statement::Accumulate(acc, target, expr), p)
+            }
+
+            "let" => match parse_assignment(tokens, pos + 1)? {
+                (Target::Variable(name), expr, p) => return Ok((Statement::Let(name, expr), p)),
+                _ => return Err("let can only declare variables, not map entries".to_string()),
+            },
+
+            "delete" if !parens && !matches!(tokens.get(param_pos), Some(Token::Index(_))) => {
+                (Statement::Delete, param_pos)
+            }
+
+            "delete" => match Target::parse(tokens, param_pos)? {
+                // a bare delete followed by an assignment such as `seen[_] = 1`
+                (Target::Entry(_, _), p)
+                    if !parens && tokens.get(p) == Some(&Token::Symbol('=')) =>
+                {
+                    (Statement::Delete, param_pos)
+                }
+                (Target::Entry(name, key), p) => (Statement::DeleteEntry(name, key), p),
+                _ => return Err("delete expects a map entry such as name[key]".to_string()),
+            },
+
+            "for" => {
+                let (var, p) = parse_id(tokens, pos + 1)?;
+
+                if tokens.get(p) != Some(&Token::Identifier("in".to_string())) {
+                    return Err(format!("expected in but received {:?}", tokens.get(p)));
+                }
+
+                let (map, mut p) = parse_id(tokens, p + 1)?;
+                expect_token(Token::Paren('{'), tokens, &mut p)?;
+
+                let seq = Seq {
+                    subnodes: parse_until(Token::Paren('}'), tokens, &mut p)?,
+                    toplevel: false,
+                };
+
+                if seq.num_ranges() != 0 {
+                    return Err("ranges are not allowed inside of a for loop".to_string());
+                }
+
+                return Ok((Statement::For(var, map, seq), p));
+            }
+
+            "emit" => {
+                let (name, mut p) = parse_id(tokens, param_pos)?;
+
+                let format = match &name[..] {
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    "logfmt" => Format::Logfmt,
+                    _ => {
+                        return Err(format!(
+                            "expected json, csv or logfmt to emit but received {}",
+                            name
+                        ))
+                    }
+                };
+
+                let names = if tokens.get(p) == Some(&Token::Paren('(')) {
+                    p += 1;
+                    let mut names = Vec::new();
+
+                    while tokens.get(p) != Some(&Token::Paren(')')) {
+                        if !names.is_empty() {
+                            expect_token(Token::Comma, tokens, &mut p)?;
+                        }
+
+                        let (name, next) = parse_id(tokens, p)?;
+                        names.push(name);
+                        p = next;
+                    }
+
+                    p += 1;
+                    Some(names)
+                } else {
+                    None
+                };
+
+                (Statement::Emit(format, names), p)
+            }
+
+            "def" => return Err("procedures can only be defined at the top level".to_string()),
+            "include" => return Err("include is only allowed at the top level".to_string()),
+
             #[cfg(feature = "bind")]
             "bind" => {
                 let (id, p) = parse_id(tokens, param_pos)?;
                 (Statement::Bind(id), p)
             }
 
+            _ if parens => {
+                let mut p = param_pos;
+                let mut args = Vec::new();
+
+                while tokens.get(p) != Some(&Token::Paren(')')) {
+                    if !args.is_empty() {
+                        expect_token(Token::Comma, tokens, &mut p)?;
+                    }
+
+                    args.push(Expression::parse_mut(tokens, &mut p)?);
+                }
+
+                (Statement::Call(id.to_string(), args), p)
+            }
+
             _ => {
                 return Err(format!(
                     "expected a valid statement but received invalid one {:?}",
@@ -403,27 +1044,152 @@ impl Parsable for Statement {
 
 impl Parsable for Expression {
     fn parse(tokens: &[Token], pos: usize) -> Result<(Expression, usize), String> {
+        Expression::parse_sum(tokens, pos)
+    }
+}
+
+impl Expression {
+    fn parse_sum(tokens: &[Token], pos: usize) -> Result<(Self, usize), String> {
+        let (mut lh, mut pos) = Expression::parse_product(tokens, pos)?;
+
+        loop {
+            let op = match tokens.get(pos) {
+                Some(Token::Symbol('+')) => Operator::Add,
+                Some(Token::Symbol('-')) => Operator::Sub,
+                _ => return Ok((lh, pos)),
+            };
+
+            let (rh, next) = Expression::parse_product(tokens, pos + 1)?;
+
+            lh = Expression::Arithmetic(op, Box::new(lh), Box::new(rh));
+            pos = next;
+        }
+    }
+
+    fn parse_product(tokens: &[Token], pos: usize) -> Result<(Self, usize), String> {
+        let (mut lh, mut pos) = Expression::parse_unary(tokens, pos)?;
+
+        loop {
+            let op = match tokens.get(pos) {
+                Some(Token::Symbol('*')) => Operator::Mul,
+                Some(Token::Symbol('/')) => Operator::Div,
+                Some(Token::Symbol('%')) => Operator::Mod,
+                _ => return Ok((lh, pos)),
+            };
+
+            let (rh, next) = Expression::parse_unary(tokens, pos + 1)?;
+
+            lh = Expression::Arithmetic(op, Box::new(lh), Box::new(rh));
+            pos = next;
+        }
+    }
+
+    fn parse_unary(tokens: &[Token], pos: usize) -> Result<(Self, usize), String> {
+        if tokens.get(pos) == Some(&Token::Symbol('-')) {
+            let (sub, next) = Expression::parse_unary(tokens, pos + 1)?;
+
+            return Ok((Expression::Neg(Box::new(sub)), next));
+        }
+
+        Expression::parse_single(tokens, pos)
+    }
+
+    fn parse_single(tokens: &[Token], pos: usize) -> Result<(Self, usize), String> {
         let token = guard_eof!(tokens.get(pos));
 
-        if let Token::String(content, interpolatable) = token {
-            return Ok((
+        match token {
+            Token::String(content, interpolatable) => Ok((
                 Expression::String(content.to_string(), *interpolatable),
                 pos + 1,
-            ));
+            )),
+
+            Token::Identifier(name) if tokens.get(pos + 1) == Some(&Token::Paren('(')) => {
+                let mut pos = pos + 2;
+                let mut args = Vec::new();
+
+                while tokens.get(pos) != Some(&Token::Paren(')')) {
+                    if !args.is_empty() {
+                        expect_token(Token::Comma, tokens, &mut pos)?;
+                    }
+
+                    args.push(Expression::parse_mut(tokens, &mut pos)?);
+                }
+
+                Ok((Expression::Call(name.to_string(), args), pos + 1))
+            }
+
+            Token::Identifier(name) => Ok((Expression::Identifier(name.to_string()), pos + 1)),
+
+            Token::Index(name) => {
+                let mut pos = pos + 1;
+                let key = Expression::parse_mut(tokens, &mut pos)?;
+                expect_token(Token::Paren(']'), tokens, &mut pos)?;
+
+                Ok((Expression::Index(name.to_string(), Box::new(key)), pos))
+            }
+
+            Token::Number(num) => Ok((Expression::Integer(*num), pos + 1)),
+
+            Token::Float(num) => Ok((Expression::Float(*num), pos + 1)),
+
+            Token::Path(path) => Ok((Expression::Path(path.clone()), pos + 1)),
+
+            Token::Paren('(') => {
+                let mut pos = pos + 1;
+                let expr = Expression::parse_mut(tokens, &mut pos)?;
+                expect_token(Token::Paren(')'), tokens, &mut pos)?;
+
+                Ok((expr, pos))
+            }
+
+            _ => Err(format!(
+                "Expected litteral or identifier but received: {:?}",
+                token
+            )),
         }
+    }
+}
 
-        if let Some(Token::Identifier(name)) = tokens.get(pos) {
-            return Ok((Expression::Identifier(name.to_string()), pos + 1));
-        };
+impl Parsable for Target {
+    fn parse(tokens: &[Token], pos: usize) -> Result<(Target, usize), String> {
+        match guard_eof!(tokens.get(pos)) {
+            Token::Identifier(name) if name == "_" => {
+                Err("can not assign to _, use set instead".to_string())
+            }
 
-        Err(format!(
-            "Expected litteral or identifier but received: {:?}",
-            tokens.get(pos)
-        ))
+            Token::Identifier(name) if BUILTINS.contains(&name.as_str()) => {
+                Err(format!("can not assign to the built-in variable {}", name))
+            }
+
+            Token::Identifier(name) => Ok((Target::Variable(name.to_string()), pos + 1)),
+
+            Token::Index(name) => {
+                let mut pos = pos + 1;
+                let key = Expression::parse_mut(tokens, &mut pos)?;
+                expect_token(Token::Paren(']'), tokens, &mut pos)?;
+
+                Ok((Target::Entry(name.to_string(), key), pos))
+            }
+
+            token => Err(format!("expected identifier but received {:?}", token)),
+        }
     }
 }
 
-#[cfg(feature = "bind")]
+fn parse_assignment(tokens: &[Token], pos: usize) -> Result<(Target, Expression, usize), String> {
+    let (target, mut pos) = Target::parse(tokens, pos)?;
+
+    expect_token(Token::Symbol('='), tokens, &mut pos)?;
+
+    let expr = Expression::parse_mut(tokens, &mut pos)?;
+
+    Ok((target, expr, pos))
+}
+
+fn takes_expression(id: &str) -> bool {
+    matches!(id, "print" | "read" | "write" | "exec" | "append" | "set")
+}
+
 fn parse_id(tokens: &[Token], pos: usize) -> Result<(String, usize), String> {
     let token = guard_eof!(tokens.get(pos));
 
@@ -5,11 +5,7 @@ use std::panic::panic_any;
 macro_rules! seq {
     (tl $($ast: expr),*) => {
         {
-            let mut subnodes = Vec::new();
-
-            $(
-                subnodes.push($ast);
-            )*
+            let subnodes = vec![$($ast),*];
 
             Seq { subnodes, toplevel: true }
         }
@@ -17,11 +13,7 @@ macro_rules! seq {
 
     ($($ast: expr),*) => {
         {
-            let mut subnodes = Vec::new();
-
-            $(
-                subnodes.push($ast);
-            )*
+            let subnodes = vec![$($ast),*];
 
             Seq { subnodes, toplevel: false }
         }
@@ -49,19 +41,27 @@ macro_rules! id {
     };
 }
 
+macro_rules! range {
+    ($start:expr, $end:expr) => {
+        Range {
+            start: $start,
+            end: $end,
+            inclusive: false,
+            enter: None,
+            exit: None,
+        }
+    };
+}
+
 macro_rules! selector {
     (m$ast: expr) => { Selector::Match($ast) };
     (!$ast: expr) => { Selector::Negate(Box::new($ast)) };
     (a$lh : expr, $rh : expr) => { Selector::Conjunction(Box::new($lh), Box::new($rh)) };
     (o$lh : expr, $rh : expr) => { Selector::Disjunction(Box::new($lh), Box::new($rh)) };
-    (-$start:expr, $end:expr) => { Selector::Range(Range($start, $end)) };
+    (-$start:expr, $end:expr) => { Selector::Range(range!($start, RangeEnd::Match($end))) };
     ($($ast: expr),*) => {
         {
-            let mut patterns = Vec::new();
-
-            $(
-                patterns.push($ast);
-            )*
+            let patterns = vec![$($ast),*];
 
             Selector::Pattern(PatternMatch { patterns })
         }
@@ -391,3 +391,482 @@ fn parse_selector_op_precedence() {
         ])
     )
 }
+
+#[test]
+fn parse_arithmetic_precedence() {
+    let bin = |op, lh, rh| Expression::Arithmetic(op, Box::new(lh), Box::new(rh));
+
+    assert_eq!(
+        parse(lex("print 1 + x * 2.5 - -y % 3").unwrap()),
+        Ok(seq![tl Body::Bare(Statement::Print(bin(
+            Operator::Sub,
+            bin(
+                Operator::Add,
+                Expression::Integer(1),
+                bin(Operator::Mul, id!("x"), Expression::Float(2.5))
+            ),
+            bin(
+                Operator::Mod,
+                Expression::Neg(Box::new(id!("y"))),
+                Expression::Integer(3)
+            )
+        )))])
+    );
+}
+
+#[test]
+fn parse_arithmetic_parens() {
+    let bin = |op, lh, rh| Expression::Arithmetic(op, Box::new(lh), Box::new(rh));
+
+    assert_eq!(
+        parse(lex("print((a + b) / 2)").unwrap()),
+        Ok(seq![tl Body::Bare(Statement::Print(bin(
+            Operator::Div,
+            bin(Operator::Add, id!("a"), id!("b")),
+            Expression::Integer(2)
+        )))])
+    );
+}
+
+#[test]
+fn parse_let_and_assign() {
+    assert_eq!(
+        parse(lex("let x = 1 + y\ncount = count + 1").unwrap()),
+        Ok(seq![tl
+            Body::Bare(Statement::Let(
+                "x".to_string(),
+                Expression::Arithmetic(Operator::Add, Box::new(Expression::Integer(1)), Box::new(id!("y")))
+            )),
+            Body::Bare(Statement::Assign(
+                Target::Variable("count".to_string()),
+                Expression::Arithmetic(Operator::Add, Box::new(id!("count")), Box::new(Expression::Integer(1)))
+            ))
+        ])
+    );
+
+    assert!(parse(lex("_ = 'x'").unwrap()).is_err());
+}
+
+#[test]
+fn parse_accumulators() {
+    assert_eq!(
+        parse(lex("incr errors\nsum total, n\nmax(big, n)\npush users, user").unwrap()),
+        Ok(seq![tl
+            Body::Bare(Statement::Accumulate(Accumulator::Sum, Target::Variable("errors".to_string()), Expression::Integer(1))),
+            Body::Bare(Statement::Accumulate(Accumulator::Sum, Target::Variable("total".to_string()), id!("n"))),
+            Body::Bare(Statement::Accumulate(Accumulator::Max, Target::Variable("big".to_string()), id!("n"))),
+            Body::Bare(Statement::Accumulate(Accumulator::Push, Target::Variable("users".to_string()), id!("user")))
+        ])
+    );
+}
+
+#[test]
+fn parse_map_statements() {
+    assert_eq!(
+        parse(lex("count[ip] = count[ip] + 1\nincr seen[ip]\ndelete seen[ip]").unwrap()),
+        Ok(seq![tl
+            Body::Bare(Statement::Assign(
+                Target::Entry("count".to_string(), id!("ip")),
+                Expression::Arithmetic(
+                    Operator::Add,
+                    Box::new(Expression::Index("count".to_string(), Box::new(id!("ip")))),
+                    Box::new(Expression::Integer(1))
+                )
+            )),
+            Body::Bare(Statement::Accumulate(
+                Accumulator::Sum,
+                Target::Entry("seen".to_string(), id!("ip")),
+                Expression::Integer(1)
+            )),
+            Body::Bare(Statement::DeleteEntry("seen".to_string(), id!("ip")))
+        ])
+    );
+}
+
+#[test]
+fn parse_for_and_contains() {
+    assert_eq!(
+        parse(lex("$ { for ip in count { 'x' in count print ip } }").unwrap()),
+        Ok(seq![tl Body::Guard(
+            selector!(m Match::End),
+            seq![Body::Bare(Statement::For(
+                "ip".to_string(),
+                "count".to_string(),
+                seq![Body::Single(
+                    Selector::Contains(quote!(s"x"), "count".to_string()),
+                    Statement::Print(id!("ip"))
+                )]
+            ))]
+        )])
+    );
+
+    assert!(parse(lex("for x in m { 1,2 print x }").unwrap()).is_err());
+}
+
+#[test]
+fn parse_index_needs_adjacent_bracket() {
+    assert_eq!(
+        parse(lex("print x [_, y] print y").unwrap()),
+        Ok(seq![tl
+            Body::Bare(Statement::Print(id!("x"))),
+            Body::Single(
+                selector![Pattern::Identifier("_".to_string()), Pattern::Identifier("y".to_string())],
+                Statement::Print(id!("y"))
+            )
+        ])
+    );
+}
+
+#[test]
+fn parse_function_call() {
+    assert_eq!(
+        parse(lex("print pad(upper(name), -10) + len(x)").unwrap()),
+        Ok(seq![tl Body::Bare(Statement::Print(Expression::Arithmetic(
+            Operator::Add,
+            Box::new(Expression::Call(
+                "pad".to_string(),
+                vec![
+                    Expression::Call("upper".to_string(), vec![id!("name")]),
+                    Expression::Neg(Box::new(Expression::Integer(10)))
+                ]
+            )),
+            Box::new(Expression::Call("len".to_string(), vec![id!("x")]))
+        )))])
+    );
+}
+
+#[test]
+fn parse_else_chain() {
+    assert_eq!(
+        parse(lex("/a/ { print 'a' } else /b/ print 'b' else { print 'c' }").unwrap()),
+        Ok(seq![tl Body::Branch(
+            selector!(m rmatch!("a")),
+            seq![Body::Bare(Statement::Print(quote!(s"a")))],
+            seq![Body::Branch(
+                selector!(m rmatch!("b")),
+                seq![Body::Bare(Statement::Print(quote!(s"b")))],
+                seq![Body::Bare(Statement::Print(quote!(s"c")))]
+            )]
+        )])
+    );
+}
+
+#[test]
+fn parse_comparisons() {
+    assert_eq!(
+        parse(lex("code >= 500 & !user == 'root' | path ~ /git/ print _").unwrap()),
+        Ok(seq![tl Body::Single(
+            selector!(o
+                selector!(a
+                    Selector::Compare(id!("code"), Comparison::GreaterEqual(Expression::Integer(500))),
+                    selector!(! Selector::Compare(id!("user"), Comparison::Equal(quote!(s"root"))))
+                ),
+                Selector::Compare(id!("path"), Comparison::Matches(Box::new(Regex::new("git").unwrap())))
+            ),
+            Statement::Print(id!("_"))
+        )])
+    );
+}
+
+#[test]
+fn parse_paths() {
+    assert_eq!(
+        parse(lex(".http.status >= 500 print .msg").unwrap()),
+        Ok(seq![tl Body::Single(
+            Selector::Compare(
+                Expression::Path(vec!["http".to_string(), "status".to_string()]),
+                Comparison::GreaterEqual(Expression::Integer(500))
+            ),
+            Statement::Print(Expression::Path(vec!["msg".to_string()]))
+        )])
+    );
+}
+
+#[test]
+fn parse_emit() {
+    assert_eq!(
+        parse(lex("emit json\nemit csv(a, b)").unwrap()),
+        Ok(seq![tl
+            Body::Bare(Statement::Emit(Format::Json, None)),
+            Body::Bare(Statement::Emit(
+                Format::Csv,
+                Some(vec!["a".to_string(), "b".to_string()])
+            ))
+        ])
+    );
+
+    assert!(parse(lex("emit xml").unwrap()).is_err());
+}
+
+#[test]
+fn parse_logfmt() {
+    assert_eq!(
+        parse(lex("{level: 'error', msg, 'trace-id': id} print msg").unwrap()),
+        Ok(seq![tl Body::Single(
+            Selector::Logfmt(LogfmtMatch {
+                pairs: vec![
+                    ("level".to_string(), Pattern::String("error".to_string(), false)),
+                    ("msg".to_string(), Pattern::Identifier("msg".to_string())),
+                    ("trace-id".to_string(), Pattern::Identifier("id".to_string())),
+                ]
+            }),
+            Statement::Print(id!("msg"))
+        )])
+    );
+
+    assert!(parse(lex("{'trace-id'} print _").unwrap()).is_err());
+}
+
+#[test]
+fn parse_columns() {
+    assert_eq!(
+        parse(lex("[0..8 'root', 65.. cmd] print cmd").unwrap()),
+        Ok(seq![tl Body::Single(
+            Selector::Columns(ColumnMatch {
+                columns: vec![
+                    (
+                        Column { start: 0, end: Some(8) },
+                        Pattern::String("root".to_string(), false)
+                    ),
+                    (
+                        Column { start: 65, end: None },
+                        Pattern::Identifier("cmd".to_string())
+                    ),
+                ]
+            }),
+            Statement::Print(id!("cmd"))
+        )])
+    );
+
+    assert_eq!(
+        parse(lex("[9..3 a] print a").unwrap()),
+        Err("the column 9..3 is empty".to_string())
+    );
+    assert_eq!(
+        parse(lex("[0..-3 a] print a").unwrap()),
+        Err("the column 0..-3 ends before the line".to_string())
+    );
+    assert_eq!(
+        parse(lex("[-2..3 a] print a").unwrap()),
+        Err("the column -2.. starts before the line".to_string())
+    );
+}
+
+#[test]
+fn parse_switch() {
+    assert_eq!(
+        parse(lex("match { /a/ => print 'a', [_, x] => { print x } _ => print 'c' }").unwrap()),
+        Ok(seq![tl Body::Switch(
+            vec![
+                Arm(
+                    selector!(m rmatch!("a")),
+                    seq![Body::Bare(Statement::Print(quote!(s"a")))]
+                ),
+                Arm(
+                    selector!(
+                        Pattern::Identifier("_".to_string()),
+                        Pattern::Identifier("x".to_string())
+                    ),
+                    seq![Body::Bare(Statement::Print(id!("x")))]
+                ),
+            ],
+            Some(seq![Body::Bare(Statement::Print(quote!(s"c")))])
+        )])
+    );
+}
+
+#[test]
+fn parse_switch_default_last() {
+    assert!(parse(lex("match { _ => print 'a', /b/ => print 'b' }").unwrap()).is_err());
+}
+
+#[test]
+fn parse_switch_lifecycle() {
+    assert!(parse(lex("match { ^ => print 'a', $ => print 'b' }").unwrap()).is_ok());
+    assert_eq!(
+        parse(lex("match { ^ => print 'begin'; /a/ => print 'A' }").unwrap()),
+        Err("a match can not mix begin and end arms with line arms".to_string())
+    );
+    assert!(parse(lex("match { beginfile => print 'a', _ => print 'b' }").unwrap()).is_err());
+}
+
+#[test]
+fn parse_procedure() {
+    assert_eq!(
+        parse(lex("def show(a, b) { print a }\nshow(_, 'x')").unwrap()),
+        Ok(seq![tl
+            Body::Def(Procedure {
+                name: "show".to_string(),
+                params: vec!["a".to_string(), "b".to_string()],
+                body: seq![Body::Bare(Statement::Print(id!("a")))],
+            }),
+            Body::Bare(Statement::Call("show".to_string(), vec![id!("_"), quote!(s"x")]))
+        ])
+    );
+}
+
+#[test]
+fn parse_procedure_errors() {
+    assert!(parse(lex("/a/ { def f() { print _ } }").unwrap()).is_err());
+    assert!(parse(lex("def f() { /a/,/b/ print _ }").unwrap()).is_err());
+    assert!(parse(lex("def f(a, a) { print a }").unwrap()).is_err());
+    assert!(parse(lex("def f() { }\ndef f() { }").unwrap()).is_err());
+}
+
+#[test]
+fn parse_builtin_assignment() {
+    assert!(parse(lex("NR = 1").unwrap()).is_err());
+    assert!(parse(lex("let FILENAME = 'a'").unwrap()).is_err());
+}
+
+#[test]
+fn parse_file_events() {
+    assert_eq!(
+        parse(lex("beginfile print FILENAME\nendfile print FNR").unwrap()),
+        Ok(seq![tl
+            Body::Single(selector!(m Match::BeginFile), Statement::Print(id!("FILENAME"))),
+            Body::Single(selector!(m Match::EndFile), Statement::Print(id!("FNR")))
+        ])
+    );
+}
+
+#[test]
+fn parse_next() {
+    assert_eq!(
+        parse(lex("/^#/ next\n/a/ print _").unwrap()),
+        Ok(seq![tl
+            Body::Single(selector!(m rmatch!("^#")), Statement::Next),
+            Body::Single(selector!(m rmatch!("a")), Statement::Print(id!("_")))
+        ])
+    );
+}
+
+#[test]
+fn parse_delete() {
+    assert_eq!(
+        parse(lex("/x/ delete\n/y/ { delete }\ndelete seen[_]").unwrap()),
+        Ok(seq![tl
+            Body::Single(selector!(m rmatch!("x")), Statement::Delete),
+            Body::Guard(
+                selector!(m rmatch!("y")),
+                seq![Body::Bare(Statement::Delete)]
+            ),
+            Body::Bare(Statement::DeleteEntry("seen".to_string(), id!("_")))
+        ])
+    );
+}
+
+#[test]
+fn parse_delete_before_entry() {
+    assert_eq!(
+        parse(lex("/x/ {\n  delete\n  seen[_] = 1\n}").unwrap()),
+        Ok(seq![tl Body::Guard(
+            selector!(m rmatch!("x")),
+            seq![
+                Body::Bare(Statement::Delete),
+                Body::Bare(Statement::Assign(
+                    Target::Entry("seen".to_string(), id!("_")),
+                    Expression::Integer(1)
+                ))
+            ]
+        )])
+    );
+}
+
+#[test]
+fn parse_delete_named_variable() {
+    assert!(parse(lex("let delete = 1\nprint delete\nseen[_] = 1").unwrap()).is_ok());
+}
+
+#[test]
+fn parse_hold_space() {
+    assert_eq!(
+        parse(lex("hold; hold_append; fetch; fetch_append; swap; pull").unwrap()),
+        Ok(seq![tl
+            Body::Bare(Statement::Hold(HoldSpace::Hold)),
+            Body::Bare(Statement::Hold(HoldSpace::HoldAppend)),
+            Body::Bare(Statement::Hold(HoldSpace::Fetch)),
+            Body::Bare(Statement::Hold(HoldSpace::FetchAppend)),
+            Body::Bare(Statement::Hold(HoldSpace::Swap)),
+            Body::Bare(Statement::Pull)
+        ])
+    );
+}
+
+#[test]
+fn parse_step() {
+    assert_eq!(
+        parse(lex("0~4 print _").unwrap()),
+        Ok(seq![tl Body::Single(
+            selector!(m Match::Step(0, 4)),
+            Statement::Print(id!("_"))
+        )])
+    );
+}
+
+#[test]
+fn parse_range_ends() {
+    assert_eq!(
+        parse(lex("/a/,+5 print _\n/b/,~4 print _").unwrap()),
+        Ok(seq![tl
+            Body::Single(
+                Selector::Range(range!(rmatch!("a"), RangeEnd::Count(5))),
+                Statement::Print(id!("_"))
+            ),
+            Body::Single(
+                Selector::Range(range!(rmatch!("b"), RangeEnd::Multiple(4))),
+                Statement::Print(id!("_"))
+            )
+        ])
+    );
+}
+
+#[test]
+fn parse_inclusive_range() {
+    assert_eq!(
+        parse(lex("/a/,=/b/ print _").unwrap()),
+        Ok(seq![tl Body::Single(
+            Selector::Range(Range {
+                inclusive: true,
+                ..range!(rmatch!("a"), RangeEnd::Match(rmatch!("b")))
+            }),
+            Statement::Print(id!("_"))
+        )])
+    );
+}
+
+#[test]
+fn parse_range_hooks() {
+    assert_eq!(
+        parse(lex("/a/,/b/ { enter { print 'in' } print _ exit { print 'out' } }").unwrap()),
+        Ok(seq![tl Body::Guard(
+            Selector::Range(Range {
+                enter: Some(seq![Body::Bare(Statement::Print(quote!(s"in")))]),
+                exit: Some(seq![Body::Bare(Statement::Print(quote!(s"out")))]),
+                ..range!(rmatch!("a"), RangeEnd::Match(rmatch!("b")))
+            }),
+            seq![Body::Bare(Statement::Print(id!("_")))]
+        )])
+    );
+}
+
+#[test]
+fn parse_range_hooks_once() {
+    assert!(parse(lex("/a/,/b/ { enter { print 'a' } enter { print 'b' } }").unwrap()).is_err());
+    assert!(parse(lex("/a/,/b/ { exit { /c/,/d/ print _ } }").unwrap()).is_err());
+}
+
+#[test]
+fn parse_from_last() {
+    assert_eq!(
+        parse(lex("last print _\n-3 print _\nlast = _").unwrap()),
+        Ok(seq![tl
+            Body::Single(selector!(m Match::Index(-1)), Statement::Print(id!("_"))),
+            Body::Single(selector!(m Match::Index(-3)), Statement::Print(id!("_"))),
+            Body::Bare(Statement::Assign(
+                Target::Variable("last".to_string()),
+                id!("_")
+            ))
+        ])
+    );
+}
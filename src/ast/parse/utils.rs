@@ -9,7 +9,7 @@ pub(super) fn parse_until<T: Parsable>(
     let mut subnodes = Vec::new();
 
     while Some(&token) != tokens.get(*pos) {
-        subnodes.push(<T>::parse_mut(&tokens, pos)?);
+        subnodes.push(<T>::parse_mut(tokens, pos)?);
     }
 
     expect_token(token, tokens, pos)?;
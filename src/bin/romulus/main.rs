@@ -13,7 +13,7 @@ extern crate regex;
 use ansi_term::Colour::*;
 use clap::{App, Arg, ArgGroup, ArgMatches};
 use regex::Regex;
-use romulus::Interpreter;
+use romulus::{Delimited, Input, Interpreter, InvalidJson, RecordSeparator};
 use std::fs::{self, File};
 use std::io::{stdin, stdout, BufReader, Write};
 use std::process;
@@ -73,6 +73,69 @@ fn main() {
                 .takes_value(true)
                 .help("sepeartes patterns in a line"),
         )
+        .arg(
+            Arg::with_name("rs")
+                .long("rs")
+                .takes_value(true)
+                .help("seperates records with a literal string instead of newlines"),
+        )
+        .arg(
+            Arg::with_name("rs-regex")
+                .long("rs-regex")
+                .takes_value(true)
+                .help("seperates records with a regex instead of newlines"),
+        )
+        .arg(
+            Arg::with_name("null")
+                .short("z")
+                .long("null")
+                .help("seperates records with NUL bytes"),
+        )
+        .arg(
+            Arg::with_name("paragraph")
+                .short("p")
+                .long("paragraph")
+                .help("seperates records with blank lines"),
+        )
+        .arg(
+            Arg::with_name("csv")
+                .long("csv")
+                .help("splits lines into comma seperated fields with quoting"),
+        )
+        .arg(
+            Arg::with_name("tsv")
+                .long("tsv")
+                .help("splits lines into tab seperated fields with quoting"),
+        )
+        .arg(
+            Arg::with_name("header")
+                .short("H")
+                .long("header")
+                .help("the first line of each input names the fields as variables"),
+        )
+        .arg(
+            Arg::with_name("fixed-width")
+                .short("w")
+                .long("fixed-width")
+                .requires("header")
+                .conflicts_with_all(&["csv", "tsv"])
+                .help("cuts lines into fixed width columns inferred from the header"),
+        )
+        .arg(
+            Arg::with_name("jsonl")
+                .short("j")
+                .long("jsonl")
+                .conflicts_with_all(&["csv", "tsv", "header"])
+                .help("reads each line as a JSON object for paths like .a.b"),
+        )
+        .arg(
+            Arg::with_name("invalid-json")
+                .long("invalid-json")
+                .takes_value(true)
+                .possible_values(&["skip", "pass", "error"])
+                .default_value("error")
+                .help("selects what happens to lines which are not JSON objects"),
+        )
         .arg(
             Arg::with_name("lint")
                 .short("l")
@@ -90,12 +153,27 @@ fn main() {
                 .takes_value(false)
                 .help("disable implicit line printing"),
         )
+        .arg(
+            Arg::with_name("drop-next")
+                .long("drop-next")
+                .takes_value(false)
+                .help("do not implicitly print lines skipped with next"),
+        )
+        .arg(
+            Arg::with_name("concat")
+                .long("concat")
+                .conflicts_with("inplace")
+                .takes_value(false)
+                .help("process all inputs as one stream instead of each on its own"),
+        )
         .group(
             ArgGroup::with_name("program")
                 .args(&["file", "expr", "features"])
                 .required(true),
         )
         .group(ArgGroup::with_name("output_flow").args(&["output", "inplace"]))
+        .group(ArgGroup::with_name("records").args(&["rs", "rs-regex", "null", "paragraph"]))
+        .group(ArgGroup::with_name("delimited").args(&["csv", "tsv"]))
         .arg(Arg::with_name("inputs").min_values(1))
         .get_matches();
 
@@ -127,6 +205,51 @@ fn create_interpreter(matches: &ArgMatches) -> Interpreter {
         }
     }
 
+    if let Some(rs) = matches.value_of("rs") {
+        builder.record_sep(RecordSeparator::Literal(rs.to_string()));
+    }
+
+    if let Some(rs) = matches.value_of("rs-regex") {
+        match Regex::new(rs) {
+            Ok(regex) => {
+                builder.record_sep(RecordSeparator::Pattern(regex));
+            }
+            Err(msg) => error!("Error parsing record seperator: {}", msg),
+        }
+    }
+
+    if matches.is_present("null") {
+        builder.record_sep(RecordSeparator::Nul);
+    }
+
+    if matches.is_present("paragraph") {
+        builder.record_sep(RecordSeparator::Paragraph);
+    }
+
+    if matches.is_present("csv") {
+        builder.delimited(Delimited::Csv);
+    }
+
+    if matches.is_present("tsv") {
+        builder.delimited(Delimited::Tsv);
+    }
+
+    if matches.is_present("header") {
+        builder.header(true);
+    }
+
+    if matches.is_present("fixed-width") {
+        builder.fixed_width(true);
+    }
+
+    if matches.is_present("jsonl") {
+        builder.json(match matches.value_of("invalid-json") {
+            Some("skip") => InvalidJson::Skip,
+            Some("pass") => InvalidJson::Pass,
+            _ => InvalidJson::Error,
+        });
+    }
+
     if let Some(expr) = matches.value_of("expr") {
         builder.expression(expr.to_string());
     }
@@ -135,6 +258,10 @@ fn create_interpreter(matches: &ArgMatches) -> Interpreter {
         builder.print(false);
     }
 
+    if matches.is_present("drop-next") {
+        builder.print_next(false);
+    }
+
     if let Some(filename) = matches.value_of("file") {
         builder.filename(filename.to_string());
     }
@@ -151,7 +278,7 @@ fn process_inplace<'a, I: Iterator<Item = &'a str>>(
     inputs: &'a mut I,
 ) {
     for input in inputs {
-        let fin = match File::open(&input) {
+        let fin = match File::open(input) {
             Ok(f) => f,
             Err(err) => error!("unable to read file '{}': {}", input, err),
         };
@@ -161,10 +288,12 @@ fn process_inplace<'a, I: Iterator<Item = &'a str>>(
             Err(err) => error!("unable to create temp file {}", err),
         };
 
-        interpreter.process(&mut BufReader::new(fin), &mut fout);
+        if let Err(msg) = interpreter.try_process_file(input, &mut BufReader::new(fin), &mut fout) {
+            error!("{}: {}", input, msg);
+        }
 
         if !ext.is_empty() {
-            if let Err(err) = fs::rename(&input, format!("{}.{}", input, ext)) {
+            if let Err(err) = fs::rename(input, format!("{}.{}", input, ext)) {
                 drop(fout);
                 error!("unable to rename {}.{} -> {}: {}", input, ext, input, err);
             }
@@ -194,13 +323,24 @@ fn process_streams(interpreter: Interpreter, matches: &ArgMatches) {
     };
 
     if let Some(inputs) = matches.values_of("inputs") {
-        for input in inputs {
-            let file = match File::open(input) {
-                Ok(f) => f,
-                Err(_) => error!("Unable to read {}", input),
-            };
+        let open = |input: &str| match File::open(input) {
+            Ok(f) => BufReader::new(f),
+            Err(_) => error!("Unable to read {}", input),
+        };
 
-            interpreter.process(&mut BufReader::new(file), &mut output);
+        if matches.is_present("concat") {
+            let inputs = inputs.map(|input| Input::new(input, open(input)));
+
+            if let Err(msg) = interpreter.try_process_inputs(inputs, &mut output) {
+                error!("{}", msg);
+            }
+        } else {
+            for input in inputs {
+                if let Err(msg) = interpreter.try_process_file(input, &mut open(input), &mut output)
+                {
+                    error!("{}: {}", input, msg);
+                }
+            }
         }
     } else {
         if cfg!(not(feature = "stdin-tty")) && atty::is(atty::Stream::Stdin) {
@@ -210,7 +350,9 @@ fn process_streams(interpreter: Interpreter, matches: &ArgMatches) {
         let sin = stdin();
         let mut sin_lock = sin.lock();
 
-        interpreter.process(&mut sin_lock, &mut output);
+        if let Err(msg) = interpreter.try_process(&mut sin_lock, &mut output) {
+            error!("{}", msg);
+        }
     }
 }
 
@@ -16,5 +16,6 @@ pub fn features() -> Vec<(bool, String)> {
         feature!("envvar"),
         feature!("stdin-tty"),
         feature!("bind"),
+        feature!("json"),
     ]
 }
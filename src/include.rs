@@ -0,0 +1,99 @@
+//! Loads romulus programs from files and expressions resolving includes
+
+use crate::{ast, lex};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Resolves `include "path"` directives relative to the including file
+///
+/// Each file is included at most once, so libraries shared between
+/// several included files only define their procedures once,
+/// while a file including itself (even indirectly) is an error.
+pub(crate) struct Loader {
+    /// The files currently being loaded, as canonical and given paths
+    stack: Vec<(PathBuf, PathBuf)>,
+    loaded: Vec<PathBuf>,
+
+    /// Whether the error being returned already names its file
+    located: bool,
+}
+
+impl Loader {
+    pub(crate) fn new() -> Loader {
+        Loader {
+            stack: Vec::new(),
+            loaded: Vec::new(),
+            located: false,
+        }
+    }
+
+    /// Loads a program from a file
+    pub(crate) fn file(&mut self, filename: &str) -> Result<ast::Seq, String> {
+        self.load(Path::new(filename))
+    }
+
+    /// Loads a program from an expression, includes are resolved
+    /// relative to the current working directory
+    pub(crate) fn expression(&mut self, expr: &str) -> Result<ast::Seq, String> {
+        self.parse(expr, Path::new(""), None)
+    }
+
+    fn load(&mut self, path: &Path) -> Result<ast::Seq, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("unable to read romulus file '{}': {}", path.display(), err))?;
+
+        let id = canonical(path);
+        self.stack.push((id.clone(), path.to_path_buf()));
+        self.loaded.push(id);
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let result = self.parse(&source, dir, Some(path));
+
+        self.stack.pop();
+        result
+    }
+
+    fn parse(&mut self, source: &str, dir: &Path, file: Option<&Path>) -> Result<ast::Seq, String> {
+        let result = lex::lex(source).and_then(|tokens| {
+            ast::parse_with(tokens, &mut |include| self.include(&dir.join(include)))
+        });
+
+        match (result, file) {
+            (Ok(node), _) => Ok(node),
+            (Err(err), _) if self.located => Err(err),
+            (Err(err), Some(path)) => {
+                self.located = true;
+                Err(format!("{}: {}", path.display(), err))
+            }
+            (Err(err), None) => Err(err),
+        }
+    }
+
+    fn include(&mut self, path: &Path) -> Result<ast::Seq, String> {
+        let id = canonical(path);
+
+        if let Some(start) = self.stack.iter().position(|(p, _)| p == &id) {
+            let cycle = self.stack[start..]
+                .iter()
+                .map(|(_, given)| given.as_path())
+                .chain(std::iter::once(path))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<String>>();
+
+            return Err(format!("include cycle {}", cycle.join(" -> ")));
+        }
+
+        if self.loaded.contains(&id) {
+            return Ok(ast::Seq {
+                subnodes: Vec::new(),
+                toplevel: true,
+            });
+        }
+
+        self.load(path)
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -1,16 +1,24 @@
-use crate::runtime::op::{Operation, ScopePersister, SigStatement};
-use crate::runtime::{Environment, Event, Scope};
-use crate::{ast, lex, lint};
+use crate::include::Loader;
+use crate::runtime::op::{Lookahead, Operation, ScopePersister, SigStatement};
+use crate::runtime::{Delimited, Environment, Event, InvalidJson, RecordSeparator, Records, Scope};
+use crate::{ast, lint};
 
+use ansi_term::Colour::Red;
 use regex::Regex;
-use std::fs::File;
-use std::io::{BufRead, Read, Write};
+use std::io::{self, BufRead, Write};
+use std::iter::once;
 
 /// The interpreter which processes lines with a romulus program
 pub struct Interpreter {
     node: ast::Seq,
     sep: Regex,
+    record_sep: RecordSeparator,
+    delimited: Option<Delimited>,
+    header: bool,
+    fixed_width: bool,
+    json: Option<InvalidJson>,
     implicit_print: bool,
+    print_next: bool,
 }
 
 /// Builds an interpreter
@@ -18,15 +26,108 @@ pub struct Builder {
     filename: Option<String>,
     expression: Option<String>,
     sep: Option<Regex>,
+    record_sep: Option<RecordSeparator>,
+    delimited: Option<Delimited>,
+    header: Option<bool>,
+    fixed_width: Option<bool>,
+    json: Option<InvalidJson>,
     print: Option<bool>,
+    print_next: Option<bool>,
+}
+
+/// A named input for processing several inputs as one run
+pub struct Input<'a> {
+    name: String,
+    reader: Box<dyn BufRead + 'a>,
+}
+
+impl<'a> Input<'a> {
+    /// Creates an input, the name is given to the program as `FILENAME`
+    pub fn new<R: BufRead + 'a>(name: &str, reader: R) -> Input<'a> {
+        Input {
+            name: name.to_string(),
+            reader: Box::new(reader),
+        }
+    }
 }
 
 impl Interpreter {
     /// Process an input stream and writes the results for it's romulus program to
     /// the output stream
     pub fn process<R: BufRead, W: Write>(&self, sin: &mut R, sout: &mut W) {
-        let mut iter = sin.lines();
-        let mut env = Environment::new(sout, &self.node, self.sep.clone());
+        self.process_inputs(once(Input::new("", sin)), sout)
+    }
+
+    /// Process an input file like `process`, with its name given to the program as `FILENAME`
+    pub fn process_file<R: BufRead, W: Write>(&self, filename: &str, sin: &mut R, sout: &mut W) {
+        self.process_inputs(once(Input::new(filename, sin)), sout)
+    }
+
+    /// Process several inputs as one run
+    ///
+    /// The begin and end events happen once for the whole run and line numbers
+    /// and variables carry over between inputs, while `beginfile` and `endfile`
+    /// happen around each input.
+    pub fn process_inputs<'a, I, W>(&self, inputs: I, sout: &mut W)
+    where
+        I: IntoIterator<Item = Input<'a>>,
+        W: Write,
+    {
+        if let Err(message) = self.try_process_inputs(inputs, sout) {
+            eprint!("{}{}", color!(Red, message), nl!());
+        }
+    }
+
+    /// Process an input stream like `process`,
+    /// but fails when a JSON line is invalid under `InvalidJson::Error`
+    pub fn try_process<R: BufRead, W: Write>(
+        &self,
+        sin: &mut R,
+        sout: &mut W,
+    ) -> Result<(), String> {
+        self.try_process_inputs(once(Input::new("", sin)), sout)
+    }
+
+    /// Process an input file like `process_file`,
+    /// but fails when a JSON line is invalid under `InvalidJson::Error`
+    pub fn try_process_file<R: BufRead, W: Write>(
+        &self,
+        filename: &str,
+        sin: &mut R,
+        sout: &mut W,
+    ) -> Result<(), String> {
+        self.try_process_inputs(once(Input::new(filename, sin)), sout)
+    }
+
+    /// Process several inputs like `process_inputs`,
+    /// but fails when a JSON line is invalid under `InvalidJson::Error`,
+    /// a failure stops the run without the end event
+    pub fn try_process_inputs<'a, I, W>(&self, inputs: I, sout: &mut W) -> Result<(), String>
+    where
+        I: IntoIterator<Item = Input<'a>>,
+        W: Write,
+    {
+        let records = Records::new(io::empty(), self.record_sep.clone());
+        let mut env = Environment::new(records, sout, &self.node, self.sep.clone());
+        env.delimited = self.delimited;
+        env.fixed_width = self.fixed_width;
+        env.header = self.header;
+        env.record_end = match self.record_sep {
+            RecordSeparator::Nul => "\0",
+            _ => nl!(),
+        };
+        env.lookahead = self.node.lookahead();
+
+        // inputs read ahead keep their header as well as the lines looked ahead
+        let lookahead = env.lookahead + usize::from(self.header);
+
+        env.inputs(inputs.into_iter().map(move |input| {
+            let records = Records::new(input.reader, self.record_sep.clone())
+                .lookahead(lookahead)
+                .quoted(self.delimited);
+
+            (input.name, records)
+        }));
 
         if cfg!(feature = "envvar") {
             env.push(Scope::env());
@@ -34,32 +135,95 @@ impl Interpreter {
 
         let implicit_print = !self.node.significant();
 
-        env.event = Event::Begin;
+        env.start_event(Event::Begin);
         self.node.perform(&mut env);
 
-        while let Some(Ok(line)) = iter.next() {
-            env.lineno += 1;
-            env.event = Event::Line(line);
+        while env.next_input() {
+            if !self.process_records(&mut env, implicit_print)? {
+                return Ok(());
+            }
+        }
+
+        env.start_event(Event::End);
+        self.node.persist_scope(&mut env);
+        self.node.perform(&mut env);
+
+        Ok(())
+    }
 
-            self.node.persist_scope(&mut env);
-            self.node.perform(&mut env);
+    /// Processes each record of the current input between its file events,
+    /// returns false if the program quit
+    fn process_records(&self, env: &mut Environment, implicit_print: bool) -> Result<bool, String> {
+        env.start_event(Event::BeginFile);
+        self.node.perform(env);
+
+        if self.header {
+            if let (Some(header), true) = (env.read_header(), self.prints(implicit_print)) {
+                env.print_record(&header);
+            }
+        }
+
+        while let Some(line) = env.read_record() {
+            env.start_event(Event::Line(line));
+
+            if let (Some(policy), Err(err)) = (self.json, self.parse_json(env)) {
+                match policy {
+                    InvalidJson::Skip => continue,
+                    InvalidJson::Pass => {
+                        env.print_event();
+                        continue;
+                    }
+                    InvalidJson::Error => {
+                        return Err(format!("line {}: invalid JSON: {}", env.lineno, err));
+                    }
+                }
+            }
+
+            let columns = env.columns();
+            env.push(columns);
+
+            self.node.persist_scope(env);
+            self.node.perform(env);
+
+            env.pop();
 
             if env.finished() {
-                return;
+                return Ok(false);
             }
 
-            if implicit_print && self.implicit_print {
+            let dropped = env.deleted() || (env.skipped() && !self.print_next);
+
+            if self.prints(implicit_print) && !dropped {
                 env.print_event();
             }
         }
 
-        env.event = Event::End;
-        self.node.perform(&mut env);
+        env.start_event(Event::EndFile);
+        self.node.perform(env);
+
+        Ok(true)
+    }
+
+    /// Parses the current line when reading JSON lines
+    fn parse_json(&self, env: &mut Environment) -> Result<(), String> {
+        match self.json {
+            Some(_) => env.parse_json(),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether lines are printed after the program runs for them
+    fn prints(&self, implicit_print: bool) -> bool {
+        implicit_print && self.implicit_print
     }
 
     /// Lint the current program
     pub fn lint(&self) -> Vec<lint::LintMessage> {
-        lint::lint(&self.node)
+        if self.header {
+            lint::lint_with_header(&self.node)
+        } else {
+            lint::lint(&self.node)
+        }
     }
 
     /// Create a new interpreter builder
@@ -68,19 +232,27 @@ impl Interpreter {
             filename: None,
             expression: None,
             sep: None,
+            record_sep: None,
+            delimited: None,
+            header: None,
+            fixed_width: None,
+            json: None,
             print: None,
+            print_next: None,
         }
     }
 }
 
 impl Builder {
-    /// Sets a filename to be read
+    /// Sets a filename to be read,
+    /// includes are resolved relative to this file
     pub fn filename(&mut self, filename: String) -> &mut Self {
         self.filename = Some(filename);
         self
     }
 
-    /// Sets an expression
+    /// Sets an expression,
+    /// includes are resolved relative to the current directory
     pub fn expression(&mut self, expression: String) -> &mut Self {
         self.expression = Some(expression);
         self
@@ -92,12 +264,51 @@ impl Builder {
         self
     }
 
+    /// sets the seperator between records, which are newlines by default
+    pub fn record_sep(&mut self, record_sep: RecordSeparator) -> &mut Self {
+        self.record_sep = Some(record_sep);
+        self
+    }
+
+    /// splits lines into quoted delimited fields instead of with the seperator
+    pub fn delimited(&mut self, delimited: Delimited) -> &mut Self {
+        self.delimited = Some(delimited);
+        self
+    }
+
+    /// sets whether the first line of each input is a header
+    /// naming the fields of the following lines
+    pub fn header(&mut self, header: bool) -> &mut Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// cuts lines into fixed width columns inferred from the header,
+    /// each column starts with its name and runs until the next name starts
+    pub fn fixed_width(&mut self, fixed_width: bool) -> &mut Self {
+        self.fixed_width = Some(fixed_width);
+        self
+    }
+
+    /// reads each line as a JSON object for paths like `.a.b` to look into,
+    /// with the given policy for lines which are not valid JSON
+    pub fn json(&mut self, invalid: InvalidJson) -> &mut Self {
+        self.json = Some(invalid);
+        self
+    }
+
     /// sets the implicit printing
     pub fn print(&mut self, print: bool) -> &mut Self {
         self.print = Some(print);
         self
     }
 
+    /// sets whether lines skipped with next are still implicitly printed
+    pub fn print_next(&mut self, print_next: bool) -> &mut Self {
+        self.print_next = Some(print_next);
+        self
+    }
+
     /// Builds the interpreter
     pub fn build(&mut self) -> Result<Interpreter, String> {
         let node = match (&self.filename, &self.expression) {
@@ -108,29 +319,8 @@ impl Builder {
                 ))
             }
 
-            (Some(ref file), None) => {
-                let mut file = match File::open(file) {
-                    Ok(f) => f,
-                    Err(err) => {
-                        return Err(format!(
-                            "unable to open file romulus file '{}': {}",
-                            file, err
-                        ))
-                    }
-                };
-
-                let mut buf = String::new();
-                if let Err(err) = file.read_to_string(&mut buf) {
-                    return Err(format!("unable to read romulus file: {}", err));
-                }
-
-                let tokens = lex::lex(buf.as_ref())?;
-                ast::parse(tokens)?
-            }
-            (None, Some(expr)) => {
-                let tokens = lex::lex(expr.as_ref())?;
-                ast::parse(tokens)?
-            }
+            (Some(file), None) => Loader::new().file(file)?,
+            (None, Some(expr)) => Loader::new().expression(expr)?,
         };
 
         let sep = self
@@ -138,12 +328,45 @@ impl Builder {
             .clone()
             .unwrap_or_else(|| Regex::new(" +").unwrap());
 
+        let record_sep = self.record_sep.clone().unwrap_or_default();
+
+        if matches!(&record_sep, RecordSeparator::Literal(lit) if lit.is_empty()) {
+            return Err(String::from("the record seperator can not be empty"));
+        }
+
+        let header = self.header.unwrap_or(false);
+        let fixed_width = self.fixed_width.unwrap_or(false);
         let implicit_print = self.print.unwrap_or(true);
 
+        if fixed_width && (!header || self.delimited.is_some()) {
+            return Err(String::from(
+                "fixed width columns are inferred from a header without delimited fields",
+            ));
+        }
+
+        if self.json.is_some() {
+            if cfg!(not(feature = "json")) {
+                return Err(String::from("romulus was compiled without json support"));
+            }
+
+            if self.delimited.is_some() || header {
+                return Err(String::from(
+                    "JSON lines can not be split into delimited fields",
+                ));
+            }
+        }
+        let print_next = self.print_next.unwrap_or(true);
+
         Ok(Interpreter {
             node,
             sep,
+            record_sep,
+            delimited: self.delimited,
+            header,
+            fixed_width,
+            json: self.json,
             implicit_print,
+            print_next,
         })
     }
 }
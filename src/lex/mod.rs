@@ -14,13 +14,17 @@ pub enum Token<'a> {
     /// Represents (, {, [, ], }, )
     Paren(char),
 
-    /// Represents simple symbols like ^ and $
+    /// Represents simple symbols like ^, $ and arithmetic operators
     Symbol(char),
 
     /// Represents positive decimal numbers <br>
     /// such as `42`
     Number(i64),
 
+    /// Represents positive decimal numbers with a fractional part <br>
+    /// such as `3.14`
+    Float(f64),
+
     /// Represents a Regular Expression with flags in the form of /regex/flags <br>
     /// such as `/[a-z]+/i`
     ///
@@ -41,6 +45,16 @@ pub enum Token<'a> {
     /// i.e. _the_answer_42
     Identifier(String),
 
+    /// Represents a variable identifier directly followed by a `[`
+    ///
+    /// such as `count[` in `count[ip]`
+    Index(String),
+
+    /// Represents a path into a JSON line
+    ///
+    /// such as `.http.status`
+    Path(Vec<String>),
+
     /// Represents a string
     ///
     /// single quotes may not interpolate variables, where as double qoutes
@@ -49,6 +63,19 @@ pub enum Token<'a> {
     /// such as `'some string'`, `"Ip Address: ${ip}"`
     String(String, bool),
 
+    /// Represents a comparison operator
+    ///
+    /// one of `==`, `!=`, `<`, `<=`, `>`, `>=`, `~`, `!~`
+    Comparison(&'static str),
+
+    /// Represents `=>` which seperates a match arm from its body
+    Arrow,
+
+    /// Represents `..` in the column range of a fixed width field
+    ///
+    /// such as `0..8`
+    Dots,
+
     /// A newline character, carriage returen, or semicolon
     Newline,
 
@@ -60,12 +87,18 @@ impl Token<'_> {
     fn significant(&self) -> bool {
         match self {
             Token::Number(_) => true,
+            Token::Float(_) => true,
             Token::Paren(_) => true,
             Token::Regex(_, _) => true,
             Token::Comment(_) => false,
             Token::Identifier(_) => true,
+            Token::Index(_) => true,
+            Token::Path(_) => true,
             Token::String(_, _) => true,
             Token::Symbol(_) => true,
+            Token::Comparison(_) => true,
+            Token::Arrow => true,
+            Token::Dots => true,
             Token::Newline => false,
             Token::Comma => true,
         }
@@ -77,7 +110,7 @@ impl Token<'_> {
 ///
 /// for example newlines and comments are not significant for parsing
 /// a romulus program
-pub fn lex(buf: &str) -> Result<Vec<Token>, String> {
+pub fn lex(buf: &str) -> Result<Vec<Token<'_>>, String> {
     let tokens = full_lex(buf)?;
 
     Ok(tokens
@@ -86,8 +119,26 @@ pub fn lex(buf: &str) -> Result<Vec<Token>, String> {
         .collect::<Vec<Token>>())
 }
 
+/// Keywords which take a regex or nothing at all,
+/// so a slash following them starts a regex
+const KEYWORDS: &[&str] = &["quit", "next", "subst", "gsubst", "else"];
+
+/// Determines if a `/` should be read as division rather then the start of a regex
+///
+/// A slash is division when it follows something which can end an expression
+/// on the same line: a number, a (non keyword) identifier or a closing paren
+fn divides(tokens: &[Token]) -> bool {
+    match tokens.last() {
+        Some(Token::Number(_)) | Some(Token::Float(_)) => true,
+        Some(Token::Paren(')')) | Some(Token::Paren(']')) => true,
+        Some(Token::Path(_)) => true,
+        Some(Token::Identifier(id)) => !KEYWORDS.contains(&id.as_str()),
+        _ => false,
+    }
+}
+
 /// Lexes a given string and returns all tokens found
-pub fn full_lex(buf: &str) -> Result<Vec<Token>, String> {
+pub fn full_lex(buf: &str) -> Result<Vec<Token<'_>>, String> {
     let mut tokens = Vec::new();
     let mut it = buf.chars().enumerate().peekable();
 
@@ -105,7 +156,25 @@ pub fn full_lex(buf: &str) -> Result<Vec<Token>, String> {
         match ch {
             '0'..='9' => {
                 let end = chomp(&number_chars, &mut it);
-                tokens.push(Token::Number(get_number(&buf[start..end])));
+
+                let mut ahead = it.clone();
+                if let (Some((_, '.')), Some((_, '0'..='9'))) = (ahead.next(), ahead.next()) {
+                    it.next();
+                    let end = chomp(&number_chars, &mut it);
+                    tokens.push(Token::Float(get_float(&buf[start..end])));
+                } else {
+                    tokens.push(Token::Number(get_number(&buf[start..end])));
+                }
+            }
+
+            // a line starting with a negative number is an address counted
+            // from the last line, rather than subtracting from the line before
+            '-' if matches!(tokens.last(), None | Some(Token::Newline))
+                && buf[start + 1..].starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                it.next();
+                let end = chomp(&number_chars, &mut it);
+                tokens.push(Token::Number(-get_number(&buf[start + 1..end])));
             }
 
             '{' | '[' | '(' | '}' | ']' | ')' => {
@@ -133,6 +202,11 @@ pub fn full_lex(buf: &str) -> Result<Vec<Token>, String> {
                 tokens.push(Token::Comma);
             }
 
+            '/' if divides(&tokens) => {
+                tokens.push(Token::Symbol('/'));
+                it.next();
+            }
+
             '/' => {
                 it.next();
                 let chars = chomp_until_escaped(
@@ -174,10 +248,67 @@ pub fn full_lex(buf: &str) -> Result<Vec<Token>, String> {
             '_' | 'a'..='z' | 'A'..='Z' => {
                 let content = chomp_str(&ident_chars, &mut it);
 
-                tokens.push(Token::Identifier(content));
+                if let Some((_, '[')) = it.peek() {
+                    it.next();
+                    tokens.push(Token::Index(content));
+                } else {
+                    tokens.push(Token::Identifier(content));
+                }
+            }
+
+            '.' if buf[start + 1..].starts_with('.') => {
+                it.next();
+                it.next();
+                tokens.push(Token::Dots);
+            }
+
+            '.' => {
+                let mut path = Vec::new();
+
+                while let Some((_, '.')) = it.peek() {
+                    it.next();
+
+                    let segment = chomp_str(&ident_chars, &mut it);
+                    if segment.is_empty() {
+                        return Err("expected a key after '.' in a path".to_string());
+                    }
+
+                    path.push(segment);
+                }
+
+                tokens.push(Token::Path(path));
+            }
+
+            '=' | '!' | '<' | '>' | '~' => {
+                let first = *ch;
+                it.next();
+
+                let second = match it.peek() {
+                    Some((_, '=')) if first != '~' => Some('='),
+                    Some((_, '~')) if first == '!' => Some('~'),
+                    Some((_, '>')) if first == '=' => Some('>'),
+                    _ => None,
+                };
+
+                if second.is_some() {
+                    it.next();
+                }
+
+                tokens.push(match (first, second) {
+                    ('=', Some('>')) => Token::Arrow,
+                    ('=', Some(_)) => Token::Comparison("=="),
+                    ('!', Some('=')) => Token::Comparison("!="),
+                    ('!', Some(_)) => Token::Comparison("!~"),
+                    ('<', Some(_)) => Token::Comparison("<="),
+                    ('>', Some(_)) => Token::Comparison(">="),
+                    ('<', None) => Token::Comparison("<"),
+                    ('>', None) => Token::Comparison(">"),
+                    ('~', _) => Token::Comparison("~"),
+                    (symbol, _) => Token::Symbol(symbol),
+                });
             }
 
-            '^' | '$' | '!' | '&' | '|' => {
+            '^' | '$' | '&' | '|' | '+' | '-' | '*' | '%' | ':' => {
                 tokens.push(Token::Symbol(*ch));
                 it.next();
             }
@@ -79,3 +79,133 @@ fn escape() {
 
     assert_eq!(lex("\"\\\\\\n\\\"\""), Ok(tokens))
 }
+
+#[test]
+fn arithmetic() {
+    let tokens = vec![
+        Token::Identifier("print".to_string()),
+        Token::Paren('('),
+        Token::Identifier("port".to_string()),
+        Token::Symbol('+'),
+        Token::Float(1.5),
+        Token::Paren(')'),
+        Token::Symbol('*'),
+        Token::Number(2),
+        Token::Symbol('/'),
+        Token::Identifier("x".to_string()),
+        Token::Symbol('%'),
+        Token::Symbol('-'),
+        Token::Number(3),
+    ];
+
+    assert_eq!(lex("print(port + 1.5) * 2 / x % -3"), Ok(tokens));
+}
+
+#[test]
+fn slash_context() {
+    let tokens = vec![
+        Token::Identifier("subst".to_string()),
+        Token::Regex("a".to_string(), "".to_string()),
+        Token::Comma,
+        Token::Identifier("x".to_string()),
+        Token::Symbol('/'),
+        Token::Number(2),
+        Token::Regex("b".to_string(), "".to_string()),
+    ];
+
+    assert_eq!(lex("subst /a/, x / 2\n/b/"), Ok(tokens));
+}
+
+#[test]
+fn index() {
+    let tokens = vec![
+        Token::Index("count".to_string()),
+        Token::Identifier("ip".to_string()),
+        Token::Paren(']'),
+        Token::Identifier("x".to_string()),
+        Token::Paren('['),
+        Token::Identifier("y".to_string()),
+        Token::Paren(']'),
+    ];
+
+    assert_eq!(lex("count[ip] x [y]"), Ok(tokens));
+}
+
+#[test]
+fn comparisons() {
+    let tokens = vec![
+        Token::Comparison("=="),
+        Token::Comparison("!="),
+        Token::Comparison("<"),
+        Token::Comparison("<="),
+        Token::Comparison(">"),
+        Token::Comparison(">="),
+        Token::Comparison("~"),
+        Token::Regex("a".to_string(), "".to_string()),
+        Token::Comparison("!~"),
+        Token::Symbol('!'),
+        Token::Symbol('='),
+    ];
+
+    assert_eq!(lex("== != < <= > >= ~ /a/ !~ ! ="), Ok(tokens));
+}
+
+#[test]
+fn arrow() {
+    let tokens = vec![
+        Token::Regex("a".to_string(), "".to_string()),
+        Token::Arrow,
+        Token::Identifier("_".to_string()),
+        Token::Arrow,
+        Token::Regex("b".to_string(), "".to_string()),
+    ];
+
+    assert_eq!(lex("/a/ => _ => /b/"), Ok(tokens));
+}
+
+#[test]
+fn negative_line_start() {
+    let tokens = vec![
+        Token::Number(-3),
+        Token::Identifier("print".to_string()),
+        Token::Identifier("a".to_string()),
+        Token::Symbol('-'),
+        Token::Number(3),
+        Token::Number(-1),
+        Token::Identifier("quit".to_string()),
+    ];
+
+    assert_eq!(lex("-3 print a -3\n-1 quit"), Ok(tokens));
+}
+
+#[test]
+fn paths() {
+    let tokens = vec![
+        Token::Path(vec!["http".to_string(), "status".to_string()]),
+        Token::Comparison(">="),
+        Token::Number(500),
+        Token::Identifier("print".to_string()),
+        Token::Path(vec!["msg".to_string()]),
+    ];
+
+    assert_eq!(lex(".http.status >= 500 print .msg"), Ok(tokens));
+    assert!(lex(".a. print").is_err());
+}
+
+#[test]
+fn column_ranges() {
+    let tokens = vec![
+        Token::Paren('['),
+        Token::Number(0),
+        Token::Dots,
+        Token::Number(8),
+        Token::Identifier("user".to_string()),
+        Token::Comma,
+        Token::Number(65),
+        Token::Dots,
+        Token::Identifier("cmd".to_string()),
+        Token::Paren(']'),
+    ];
+
+    assert_eq!(lex("[0..8 user, 65.. cmd]"), Ok(tokens));
+}
@@ -1,5 +1,5 @@
-///! Utilities for the lex function.
-///! Handles low level character gathering
+//! Utilities for the lex function.
+//! Handles low level character gathering
 use std::iter::Peekable;
 use std::ops::RangeInclusive;
 
@@ -165,3 +165,9 @@ pub fn get_number(vec: &str) -> i64 {
 
     buffer
 }
+
+/// Evaulate the character buffer as a floating point number
+#[inline]
+pub fn get_float(vec: &str) -> f64 {
+    vec.parse::<f64>().unwrap()
+}
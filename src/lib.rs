@@ -49,9 +49,11 @@ mod interpreter;
 
 mod ast;
 mod features;
+mod include;
 mod lex;
 mod lint;
 mod runtime;
 
 pub use features::*;
-pub use interpreter::{Builder, Interpreter};
+pub use interpreter::{Builder, Input, Interpreter};
+pub use runtime::{Delimited, InvalidJson, RecordSeparator};
@@ -0,0 +1,221 @@
+use super::{LintMessage, Linter};
+use crate::ast::*;
+use crate::runtime::op::function;
+use std::collections::HashMap;
+
+pub(super) struct Functions();
+
+impl Linter for Functions {
+    fn lint(&self, node: &Seq) -> Vec<LintMessage> {
+        let mut messages = Vec::new();
+        let bold = ansi_term::Style::new().bold();
+
+        let procedures = node
+            .subnodes
+            .iter()
+            .filter_map(|sub| match sub {
+                Body::Def(procedure) => Some((procedure.name.as_str(), procedure.params.len())),
+                _ => None,
+            })
+            .collect::<HashMap<&str, usize>>();
+
+        for call in node.calls() {
+            match call {
+                Invocation::Function(name, argc) => match function(&name) {
+                    None => messages.push(LintMessage(format!(
+                        "Unknown function {}",
+                        color!(bold, name)
+                    ))),
+
+                    Some(func) if argc < func.min_args || argc > func.max_args => {
+                        messages.push(LintMessage(format!(
+                            "Function {} expects {} but received {}",
+                            color!(bold, name),
+                            func.arity(),
+                            argc
+                        )))
+                    }
+
+                    Some(_) => (),
+                },
+
+                Invocation::Procedure(name, argc) => match procedures.get(name.as_str()) {
+                    None => messages.push(LintMessage(format!(
+                        "Unknown procedure {}",
+                        color!(bold, name)
+                    ))),
+
+                    Some(params) if *params != argc => messages.push(LintMessage(format!(
+                        "Procedure {} expects {} but received {}",
+                        color!(bold, name),
+                        params,
+                        argc
+                    ))),
+
+                    Some(_) => (),
+                },
+            }
+        }
+
+        messages.dedup();
+        messages
+    }
+}
+
+/// A call to a built-in function or a user defined procedure
+/// with its number of arguments
+enum Invocation {
+    Function(String, usize),
+    Procedure(String, usize),
+}
+
+/// Gathers each function and procedure call
+trait Calls {
+    fn calls(&self) -> Vec<Invocation>;
+}
+
+impl Calls for Seq {
+    fn calls(&self) -> Vec<Invocation> {
+        self.subnodes.iter().flat_map(Calls::calls).collect()
+    }
+}
+
+impl Calls for Body {
+    fn calls(&self) -> Vec<Invocation> {
+        match self {
+            Body::Bare(stmt) => stmt.calls(),
+            Body::Single(sel, stmt) => {
+                let mut calls = sel.calls();
+                calls.extend(stmt.calls());
+                calls
+            }
+            Body::Guard(sel, seq) => {
+                let mut calls = sel.calls();
+                calls.extend(seq.calls());
+                calls
+            }
+            Body::Branch(sel, then, otherwise) => {
+                let mut calls = sel.calls();
+                calls.extend(then.calls());
+                calls.extend(otherwise.calls());
+                calls
+            }
+            Body::Switch(arms, default) => {
+                let mut calls = Vec::new();
+                for Arm(sel, seq) in arms {
+                    calls.extend(sel.calls());
+                    calls.extend(seq.calls());
+                }
+                if let Some(seq) = default {
+                    calls.extend(seq.calls());
+                }
+                calls
+            }
+            Body::Def(procedure) => procedure.body.calls(),
+        }
+    }
+}
+
+impl Calls for Selector {
+    fn calls(&self) -> Vec<Invocation> {
+        use Selector::*;
+
+        match self {
+            Match(_) | Pattern(_) | Logfmt(_) | Columns(_) => vec![],
+            Range(range) => range.hooks().flat_map(Calls::calls).collect(),
+            Negate(sel) => sel.calls(),
+            Conjunction(lh, rh) | Disjunction(lh, rh) => {
+                let mut calls = lh.calls();
+                calls.extend(rh.calls());
+                calls
+            }
+            Contains(key, _) => key.calls(),
+            Compare(expr, comparison) => {
+                let mut calls = expr.calls();
+                calls.extend(comparison.calls());
+                calls
+            }
+        }
+    }
+}
+
+impl Calls for Comparison {
+    fn calls(&self) -> Vec<Invocation> {
+        use Comparison::*;
+
+        match self {
+            Equal(expr) | NotEqual(expr) | Less(expr) | LessEqual(expr) | Greater(expr)
+            | GreaterEqual(expr) => expr.calls(),
+            Matches(_) | NotMatches(_) => vec![],
+        }
+    }
+}
+
+impl Calls for Statement {
+    fn calls(&self) -> Vec<Invocation> {
+        use Statement::*;
+
+        match self {
+            Print(expr)
+            | Subst(_, expr)
+            | Gsubst(_, expr)
+            | Read(expr)
+            | Write(expr)
+            | Exec(expr)
+            | Append(expr)
+            | Set(expr)
+            | Let(_, expr) => expr.calls(),
+
+            Assign(target, expr) | Accumulate(_, target, expr) => {
+                let mut calls = target.calls();
+                calls.extend(expr.calls());
+                calls
+            }
+
+            DeleteEntry(_, key) => key.calls(),
+            For(_, _, seq) => seq.calls(),
+            Call(name, args) => {
+                let mut calls = vec![Invocation::Procedure(name.to_string(), args.len())];
+                for arg in args {
+                    calls.extend(arg.calls());
+                }
+                calls
+            }
+
+            _ => vec![],
+        }
+    }
+}
+
+impl Calls for Target {
+    fn calls(&self) -> Vec<Invocation> {
+        match self {
+            Target::Variable(_) => vec![],
+            Target::Entry(_, key) => key.calls(),
+        }
+    }
+}
+
+impl Calls for Expression {
+    fn calls(&self) -> Vec<Invocation> {
+        use Expression::*;
+
+        match self {
+            String(_, _) | Identifier(_) | Integer(_) | Float(_) | Path(_) => vec![],
+            Index(_, key) => key.calls(),
+            Neg(sub) => sub.calls(),
+            Arithmetic(_, lh, rh) => {
+                let mut calls = lh.calls();
+                calls.extend(rh.calls());
+                calls
+            }
+            Call(name, args) => {
+                let mut calls = vec![Invocation::Function(name.to_string(), args.len())];
+                for arg in args {
+                    calls.extend(arg.calls());
+                }
+                calls
+            }
+        }
+    }
+}
@@ -3,20 +3,29 @@
 use crate::ast::Seq;
 use std::fmt;
 
+mod functions;
 pub(crate) mod vars;
 
 /// Lints a romulus program with standard linters
 ///
 /// Current linters
 /// 1. undefined variables
+/// 2. unknown functions and the wrong number of arguments
 pub fn lint(node: &Seq) -> Vec<LintMessage> {
     let mut results = Vec::new();
 
-    results.extend(vars::Vars().lint(&node));
+    results.extend(vars::Vars().lint(node));
+    results.extend(functions::Functions().lint(node));
 
     results
 }
 
+/// Lints a program reading a header, the variables named by the header
+/// are only known while running so variables are not checked
+pub(crate) fn lint_with_header(node: &Seq) -> Vec<LintMessage> {
+    functions::Functions().lint(node)
+}
+
 /// A wrapper type around an error message
 #[derive(Clone, PartialEq)]
 pub struct LintMessage(String);
@@ -1,5 +1,5 @@
 use crate::ast::*;
-use crate::lint::vars::provider::regex_provides;
+use crate::lint::vars::provider::{regex_provides, ScopeProvider};
 use crate::runtime::op::interpolated_variables;
 
 fn sub(base: Vec<String>, sub: Vec<String>) -> Vec<String> {
@@ -24,6 +24,10 @@ impl ScopeConsumer for Statement {
         match self {
             Print(expr) => expr.consumes(),
             Quit => vec![],
+            Next => vec![],
+            Delete => vec![],
+            Hold(_) => vec![],
+            Pull => vec![],
             Subst(regex, expr) => sub(expr.consumes(), regex_provides(regex)),
             Gsubst(regex, expr) => sub(expr.consumes(), regex_provides(regex)),
             Read(expr) => expr.consumes(),
@@ -31,6 +35,31 @@ impl ScopeConsumer for Statement {
             Exec(expr) => expr.consumes(),
             Append(expr) => expr.consumes(),
             Set(expr) => expr.consumes(),
+            Let(_, expr) => expr.consumes(),
+            Assign(target, expr) => {
+                let mut vars = target.consumes();
+                vars.extend(expr.consumes());
+                vars
+            }
+            Accumulate(_, target, expr) => {
+                let mut vars = target.consumes();
+                vars.extend(expr.consumes());
+                vars
+            }
+            DeleteEntry(name, key) => {
+                let mut vars = vec![name.to_string()];
+                vars.extend(key.consumes());
+                vars
+            }
+            For(_, map, _) => vec![map.to_string()],
+            Emit(_, names) => names.clone().unwrap_or_default(),
+            Call(_, args) => {
+                let mut vars = Vec::new();
+                for arg in args {
+                    vars.extend(arg.consumes());
+                }
+                vars
+            }
 
             #[cfg(feature = "bind")]
             Bind(id) => vec![id.to_string()],
@@ -46,6 +75,80 @@ impl ScopeConsumer for Expression {
             String(_, false) => vec![],
 
             String(content, true) => interpolated_variables(content),
+
+            Index(name, key) => {
+                let mut vars = vec![name.to_string()];
+                vars.extend(key.consumes());
+                vars
+            }
+
+            Call(_, args) => {
+                let mut vars = Vec::new();
+                for arg in args {
+                    vars.extend(arg.consumes());
+                }
+                vars
+            }
+
+            Integer(_) | Float(_) | Path(_) => vec![],
+            Neg(sub) => sub.consumes(),
+            Arithmetic(_, lh, rh) => {
+                let mut vars = lh.consumes();
+                vars.extend(rh.consumes());
+                vars
+            }
+        }
+    }
+}
+
+impl ScopeConsumer for Target {
+    fn consumes(&self) -> Vec<String> {
+        match self {
+            Target::Variable(_) => vec![],
+            Target::Entry(_, key) => key.consumes(),
+        }
+    }
+}
+
+impl ScopeConsumer for Comparison {
+    fn consumes(&self) -> Vec<String> {
+        use Comparison::*;
+
+        match self {
+            Equal(expr) | NotEqual(expr) | Less(expr) | LessEqual(expr) | Greater(expr)
+            | GreaterEqual(expr) => expr.consumes(),
+            Matches(_) | NotMatches(_) => vec![],
+        }
+    }
+}
+
+impl ScopeConsumer for Selector {
+    fn consumes(&self) -> Vec<String> {
+        use Selector::*;
+
+        match self {
+            Match(_) | Range(_) | Pattern(_) | Logfmt(_) | Columns(_) => vec![],
+            Negate(sel) => sel.consumes(),
+            Conjunction(lh, rh) => {
+                let mut vars = lh.consumes();
+                vars.extend(sub(rh.consumes(), lh.provides()));
+                vars
+            }
+            Disjunction(lh, rh) => {
+                let mut vars = lh.consumes();
+                vars.extend(rh.consumes());
+                vars
+            }
+            Contains(key, map) => {
+                let mut vars = key.consumes();
+                vars.push(map.to_string());
+                vars
+            }
+            Compare(expr, comparison) => {
+                let mut vars = expr.consumes();
+                vars.extend(comparison.consumes());
+                vars
+            }
         }
     }
 }
@@ -5,18 +5,16 @@ pub trait Globals {
 }
 
 impl Globals for Statement {
-    #[cfg(feature = "bind")]
     fn globals(&self) -> Vec<String> {
         match self {
+            #[cfg(feature = "bind")]
             Statement::Bind(id) => vec![id.to_string()],
+            Statement::Assign(target, _) => vec![target.name().to_string()],
+            Statement::Accumulate(_, target, _) => vec![target.name().to_string()],
+            Statement::For(_, _, seq) => seq.globals(),
             _ => vec![],
         }
     }
-
-    #[cfg(not(feature = "bind"))]
-    fn globals(&self) -> Vec<String> {
-        vec![]
-    }
 }
 
 impl Globals for Seq {
@@ -31,6 +29,15 @@ impl Globals for Seq {
     }
 }
 
+impl Globals for Selector {
+    fn globals(&self) -> Vec<String> {
+        match self {
+            Selector::Range(range) => range.hooks().flat_map(Globals::globals).collect(),
+            _ => vec![],
+        }
+    }
+}
+
 impl Globals for Body {
     fn globals(&self) -> Vec<String> {
         let mut v = Vec::new();
@@ -40,7 +47,28 @@ impl Globals for Body {
 
             Body::Single(_, stmt) => v.extend(stmt.globals()),
 
-            Body::Guard(_, seq) => v.extend(seq.globals()),
+            Body::Guard(sel, seq) => {
+                v.extend(sel.globals());
+                v.extend(seq.globals());
+            }
+
+            Body::Branch(sel, then, otherwise) => {
+                v.extend(sel.globals());
+                v.extend(then.globals());
+                v.extend(otherwise.globals());
+            }
+
+            Body::Def(procedure) => v.extend(procedure.body.globals()),
+
+            Body::Switch(arms, default) => {
+                for Arm(_, seq) in arms {
+                    v.extend(seq.globals());
+                }
+
+                if let Some(seq) = default {
+                    v.extend(seq.globals());
+                }
+            }
         }
 
         v
@@ -15,7 +15,10 @@ pub(super) struct Vars();
 
 impl Linter for Vars {
     fn lint(&self, node: &Seq) -> Vec<LintMessage> {
-        let mut vars = vec![vec!["_".to_string()], node.globals()];
+        let mut builtins = vec!["_".to_string()];
+        builtins.extend(BUILTINS.iter().map(ToString::to_string));
+
+        let mut vars = vec![builtins, node.globals()];
 
         if cfg!(feature = "envvar") {
             vars.push(env_vars());
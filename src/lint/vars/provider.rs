@@ -21,7 +21,12 @@ impl ScopeProvider for Selector {
             Match(m) => m.provides(),
             Range(r) => r.provides(),
             Pattern(p) => p.provides(),
+            Logfmt(l) => l.provides(),
+            Columns(c) => c.provides(),
             Negate(_) => vec![],
+            Contains(_, _) => vec![],
+            Compare(_, Comparison::Matches(regex)) => regex_provides(regex),
+            Compare(_, _) => vec![],
             Conjunction(lh, rh) => {
                 let mut vars = lh.provides();
                 vars.extend(rh.provides());
@@ -44,6 +49,15 @@ impl ScopeProvider for Selector {
     }
 }
 
+impl ScopeProvider for Statement {
+    fn provides(&self) -> Vec<String> {
+        match self {
+            Statement::Let(id, _) => vec![id.to_string()],
+            _ => vec![],
+        }
+    }
+}
+
 impl ScopeProvider for Match {
     fn provides(&self) -> Vec<String> {
         let mut results = Vec::new();
@@ -58,7 +72,13 @@ impl ScopeProvider for Match {
 
 impl ScopeProvider for Range {
     fn provides(&self) -> Vec<String> {
-        self.0.provides()
+        let mut results = self.start.provides();
+
+        if let (true, RangeEnd::Match(end)) = (self.inclusive, &self.end) {
+            results.extend(end.provides());
+        }
+
+        results
     }
 }
 
@@ -72,6 +92,23 @@ impl ScopeProvider for PatternMatch {
     }
 }
 
+impl ScopeProvider for LogfmtMatch {
+    fn provides(&self) -> Vec<String> {
+        patterns_provide(self.pairs.iter().map(|(_, pat)| pat))
+    }
+}
+
+impl ScopeProvider for ColumnMatch {
+    fn provides(&self) -> Vec<String> {
+        patterns_provide(self.columns.iter().map(|(_, pat)| pat))
+    }
+}
+
+/// The variables of the patterns of a logfmt or column match
+fn patterns_provide<'a, I: Iterator<Item = &'a Pattern>>(patterns: I) -> Vec<String> {
+    patterns.flat_map(ScopeProvider::provides).collect()
+}
+
 impl ScopeProvider for Pattern {
     fn provides(&self) -> Vec<String> {
         use Pattern::*;
@@ -79,7 +116,7 @@ impl ScopeProvider for Pattern {
         match self {
             String(_, _) => Vec::new(),
             Identifier(s) => vec![s.clone()],
-            Regex(regex) => regex_provides(&regex),
+            Regex(regex) => regex_provides(regex),
         }
     }
 }
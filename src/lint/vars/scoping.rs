@@ -1,33 +1,107 @@
 use super::consumer::ScopeConsumer;
 use super::provider::ScopeProvider;
 use crate::ast::Body::*;
-use crate::ast::Seq;
+use crate::ast::{Arm, Selector, Seq, Statement};
 
 pub(super) fn lint_vars(node: &Seq, vars: &mut Vec<Vec<String>>) -> Vec<String> {
     let mut results = Vec::new();
+    vars.push(Vec::new());
+
     for node in &node.subnodes {
         match node {
             Bare(stmt) => {
-                results.extend(check_vars(vars, stmt.consumes()));
+                results.extend(lint_statement(stmt, vars));
+
+                if let Some(block) = vars.last_mut() {
+                    block.extend(stmt.provides());
+                }
             }
 
             Single(sel, stmt) => {
+                results.extend(check_vars(vars, sel.consumes()));
                 vars.push(sel.provides());
 
-                results.extend(check_vars(vars, stmt.consumes()));
+                results.extend(lint_statement(stmt, vars));
 
                 vars.pop();
             }
 
             Guard(sel, seq) => {
+                results.extend(check_vars(vars, sel.consumes()));
                 vars.push(sel.provides());
 
+                results.extend(lint_hooks(sel, vars));
                 results.extend(lint_vars(seq, vars));
 
                 vars.pop();
             }
+
+            Branch(sel, then, otherwise) => {
+                results.extend(check_vars(vars, sel.consumes()));
+                vars.push(sel.provides());
+
+                results.extend(lint_hooks(sel, vars));
+                results.extend(lint_vars(then, vars));
+
+                vars.pop();
+
+                results.extend(lint_vars(otherwise, vars));
+            }
+
+            Def(procedure) => {
+                // procedures only see the globals and their parameters,
+                // not the top level sequence they are defined in
+                let mut frames = vars[..vars.len() - 1].to_vec();
+                frames.push(procedure.params.clone());
+
+                results.extend(lint_vars(&procedure.body, &mut frames));
+            }
+
+            Switch(arms, default) => {
+                for Arm(sel, seq) in arms {
+                    results.extend(check_vars(vars, sel.consumes()));
+                    vars.push(sel.provides());
+
+                    results.extend(lint_vars(seq, vars));
+
+                    vars.pop();
+                }
+
+                if let Some(seq) = default {
+                    results.extend(lint_vars(seq, vars));
+                }
+            }
         }
     }
+
+    vars.pop();
+    results
+}
+
+/// Lints the enter and exit blocks of a range, which see its captures
+fn lint_hooks(sel: &Selector, vars: &mut Vec<Vec<String>>) -> Vec<String> {
+    let mut results = Vec::new();
+
+    if let Selector::Range(range) = sel {
+        for hook in range.hooks() {
+            results.extend(lint_vars(hook, vars));
+        }
+    }
+
+    results
+}
+
+fn lint_statement(stmt: &Statement, vars: &mut Vec<Vec<String>>) -> Vec<String> {
+    let mut results = check_vars(vars, stmt.consumes());
+
+    if let Statement::For(var, _, seq) = stmt {
+        vars.push(vec![var.to_string()]);
+
+        results.extend(lint_vars(seq, vars));
+
+        vars.pop();
+    }
+
     results
 }
 
@@ -0,0 +1,113 @@
+/// Fields seperated by a delimiter, quoted as described by RFC 4180
+///
+/// A field in double quotes can contain the delimiter, newlines
+/// and double quotes written twice
+///
+/// ```text
+/// name,comment
+/// alice,"likes ""quotes"", commas"
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delimited {
+    /// Comma seperated values
+    Csv,
+
+    /// Tab seperated values
+    Tsv,
+}
+
+impl Delimited {
+    fn delimiter(self) -> char {
+        match self {
+            Delimited::Csv => ',',
+            Delimited::Tsv => '\t',
+        }
+    }
+
+    /// Splits a record into its unquoted fields
+    pub(crate) fn split(self, record: &str) -> Vec<String> {
+        self.scan(record, false).0
+    }
+
+    /// Whether a line ends inside of a quoted field given whether it
+    /// starts inside of one, meaning the next line is still part of the record
+    pub(crate) fn unterminated(self, line: &str, quoted: bool) -> bool {
+        self.scan(line, quoted).1
+    }
+
+    /// Splits a record into its fields, a double quote only opens
+    /// a quoted field at the start of the field, also gives
+    /// whether the last field is still quoted
+    fn scan(self, record: &str, quoted: bool) -> (Vec<String>, bool) {
+        let delimiter = self.delimiter();
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = quoted;
+
+        // a field continued from a quote on an earlier line is past its start
+        let mut started = quoted;
+        let mut chars = record.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if !started => {
+                    quoted = true;
+                    started = true;
+                }
+                ch if ch == delimiter && !quoted => {
+                    fields.push(std::mem::take(&mut field));
+                    started = false;
+                }
+                ch => {
+                    field.push(ch);
+                    started = true;
+                }
+            }
+        }
+
+        fields.push(field);
+        (fields, quoted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            Delimited::Csv.split(r#"a,"b,c",,"say ""hi""",d"e"#),
+            vec!["a", "b,c", "", "say \"hi\"", "d\"e"]
+        );
+    }
+
+    #[test]
+    fn tsv() {
+        assert_eq!(
+            Delimited::Tsv.split("a\t\"b\tc\"\td,e"),
+            vec!["a", "b\tc", "d,e"]
+        );
+    }
+
+    #[test]
+    fn embedded_newline() {
+        assert!(Delimited::Csv.unterminated("a,\"b\n", false));
+        assert!(!Delimited::Csv.unterminated("c\"\n", true));
+        assert!(Delimited::Csv.unterminated("\"\"c\n", true));
+        assert!(!Delimited::Csv.unterminated("a,\"b\nc\"", false));
+        assert_eq!(Delimited::Csv.split("a,\"b\nc\""), vec!["a", "b\nc"]);
+    }
+
+    #[test]
+    fn stray_quote() {
+        assert!(!Delimited::Csv.unterminated("tv,55\" screen", false));
+        assert!(Delimited::Csv.unterminated("tv,55\" screen,\"big", false));
+        assert!(!Delimited::Tsv.unterminated("tv\t55\" screen", false));
+    }
+}
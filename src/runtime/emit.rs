@@ -0,0 +1,156 @@
+use super::Value;
+use crate::ast::Format;
+
+/// Writes named values as a single escaped record
+pub(crate) fn record(format: Format, fields: &[(String, Value)]) -> String {
+    match format {
+        Format::Json => {
+            let pairs = fields
+                .iter()
+                .map(|(key, value)| format!("{}:{}", json_string(key), json_value(value)))
+                .collect::<Vec<String>>();
+
+            format!("{{{}}}", pairs.join(","))
+        }
+
+        Format::Csv => {
+            let row = fields
+                .iter()
+                .map(|(_, value)| csv_field(&value.to_string()))
+                .collect::<Vec<String>>();
+
+            row.join(",")
+        }
+
+        Format::Logfmt => {
+            let pairs = fields
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, logfmt_value(&value.to_string())))
+                .collect::<Vec<String>>();
+
+            pairs.join(" ")
+        }
+    }
+}
+
+/// Writes the csv header naming the fields of the following rows
+pub(crate) fn header(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| csv_field(name))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Str(s) => json_string(s),
+        Value::List(items) => {
+            let items = items
+                .iter()
+                .map(|item| json_string(item))
+                .collect::<Vec<String>>();
+            format!("[{}]", items.join(","))
+        }
+        Value::Map(map) => {
+            let pairs = map
+                .iter()
+                .map(|(key, value)| format!("{}:{}", json_string(key), json_value(value)))
+                .collect::<Vec<String>>();
+
+            format!("{{{}}}", pairs.join(","))
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len() + 2);
+    buf.push('"');
+
+    for ch in s.chars() {
+        match ch {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => buf.push(ch),
+        }
+    }
+
+    buf.push('"');
+    buf
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn logfmt_value(s: &str) -> String {
+    let quoted =
+        s.is_empty() || s.contains(|ch: char| ch == '=' || ch == '"' || ch.is_whitespace());
+
+    if !quoted {
+        return s.to_string();
+    }
+
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, Value)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), Value::Str(value.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn json() {
+        let mut fields = fields(&[("msg", "say \"hi\"\n\\"), ("ctl", "\u{1}")]);
+        fields.push(("tags".to_string(), Value::List(vec!["a".to_string()])));
+
+        assert_eq!(
+            record(Format::Json, &fields),
+            r#"{"msg":"say \"hi\"\n\\","ctl":"\u0001","tags":["a"]}"#
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let fields = fields(&[("a", "plain"), ("b", "x,y"), ("c", "say \"hi\""), ("d", "")]);
+
+        assert_eq!(record(Format::Csv, &fields), r#"plain,"x,y","say ""hi""","#);
+        assert_eq!(header(&["a".to_string(), "b,c".to_string()]), "a,\"b,c\"");
+    }
+
+    #[test]
+    fn logfmt() {
+        let fields = fields(&[
+            ("a", "plain"),
+            ("b", "two words"),
+            ("c", "k=v \"q\""),
+            ("d", ""),
+        ]);
+
+        assert_eq!(
+            record(Format::Logfmt, &fields),
+            r#"a=plain b="two words" c="k=v \"q\"" d="""#
+        );
+    }
+}
@@ -1,11 +1,14 @@
 use super::op::Valuable;
 use super::RangeCap;
-use super::{RangeScopeTracker, Scope};
-use crate::ast::Seq;
-use regex::{Regex, Split};
-use std::collections::HashMap;
+use super::{Delimited, RangeScopeTracker, Records, Scope, Value};
+use crate::ast::{Body, Column, Procedure, Seq, BUILTINS};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::io::{copy, Read, Write};
 
+/// The deepest procedure calls may nest before erroring
+pub(crate) const MAX_CALL_DEPTH: usize = 256;
+
 /// An event to be processed
 #[derive(PartialEq, Debug)]
 pub enum Event {
@@ -17,6 +20,12 @@ pub enum Event {
 
     /// The end of processing
     End,
+
+    /// The beginning of an input file
+    BeginFile,
+
+    /// The end of an input file
+    EndFile,
 }
 
 /// Embodies the current state of the program
@@ -25,31 +34,112 @@ pub struct Environment<'a> {
     /// The current line number being processed
     pub lineno: i64,
 
+    /// The current line number within the current file
+    pub(crate) file_lineno: i64,
+
+    /// The name of the current input file, empty for streams
+    pub(crate) filename: String,
+
     /// Current event being handled
     pub event: Event,
 
     pub(crate) tracker: RangeScopeTracker,
 
-    globals: HashMap<String, String>,
+    /// The hold space kept between lines
+    pub(crate) hold: String,
+
+    /// Splits lines into delimited fields instead of with the seperator
+    pub(crate) delimited: Option<Delimited>,
+
+    /// Cuts lines into fixed width columns aligned to the names in the header
+    pub(crate) fixed_width: bool,
+
+    /// Whether the first record of each input is a header
+    pub(crate) header: bool,
+
+    /// Ends each record written to the output, a NUL when records are read up to NUL bytes
+    pub(crate) record_end: &'static str,
+
+    /// The number of records the program looks ahead of the current line
+    pub(crate) lookahead: usize,
+
+    /// The names of the columns from the header of the current input
+    columns: Vec<String>,
+
+    /// Where the names are written in the header of the current input,
+    /// for cutting lines into fixed width columns
+    widths: Vec<Column>,
+
+    /// The columns of each header emit has written before its csv rows
+    pub(crate) csv_headers: HashSet<Vec<String>>,
+
+    /// The current line parsed as JSON when reading JSON lines
+    #[cfg(feature = "json")]
+    json: Option<serde_json::Value>,
+
+    globals: HashMap<String, Value>,
+    procedures: HashMap<String, &'a Procedure>,
+    depth: usize,
 
     seperator: Regex,
     scope_stack: Vec<Scope>,
+    input: Records<'a>,
+
+    /// Inputs opened before their turn to look ahead past the end of the current one
+    upcoming: VecDeque<(String, Records<'a>)>,
+
+    /// The inputs of the run which are not opened yet
+    inputs: Box<dyn Iterator<Item = (String, Records<'a>)> + 'a>,
     out: &'a mut dyn Write,
     quit: bool,
+    skipped: bool,
+    deleted: bool,
 }
 
 impl<'a> Environment<'a> {
     /// Creates a new environment
-    pub fn new<W: Write>(w: &'a mut W, node: &Seq, seperator: Regex) -> Environment<'a> {
+    pub fn new<W: Write>(
+        input: Records<'a>,
+        w: &'a mut W,
+        node: &'a Seq,
+        seperator: Regex,
+    ) -> Environment<'a> {
+        let mut procedures = HashMap::new();
+        for sub in &node.subnodes {
+            if let Body::Def(procedure) = sub {
+                procedures.insert(procedure.name.to_string(), procedure);
+            }
+        }
+
         Environment {
             lineno: 0,
+            file_lineno: 0,
+            filename: String::new(),
             event: Event::Begin,
             scope_stack: Vec::new(),
             quit: false,
+            skipped: false,
+            deleted: false,
             seperator,
+            input,
+            upcoming: VecDeque::new(),
+            inputs: Box::new(std::iter::empty()),
             out: w,
             globals: HashMap::new(),
+            procedures,
+            depth: 0,
             tracker: RangeScopeTracker::new(node.num_ranges()),
+            hold: String::new(),
+            delimited: None,
+            fixed_width: false,
+            header: false,
+            record_end: nl!(),
+            lookahead: 0,
+            columns: Vec::new(),
+            widths: Vec::new(),
+            csv_headers: HashSet::new(),
+            #[cfg(feature = "json")]
+            json: None,
         }
     }
 }
@@ -64,6 +154,10 @@ impl<'a> Environment<'a> {
             };
         }
 
+        if BUILTINS.contains(&key) {
+            return Some(self.builtin(key));
+        }
+
         for scope in self.scope_stack.iter().rev() {
             if let Some(value) = scope.get(key) {
                 return Some(value.to_string());
@@ -77,6 +171,161 @@ impl<'a> Environment<'a> {
         None
     }
 
+    fn builtin(&self, key: &str) -> String {
+        match key {
+            "NR" => self.lineno.to_string(),
+            "FNR" => self.file_lineno.to_string(),
+            "NF" => match &self.event {
+                Event::Line(line) if !line.is_empty() => self.fields(line).len(),
+                _ => 0,
+            }
+            .to_string(),
+            "FILENAME" => self.filename.clone(),
+            "OFFSET" => self.input.offset().to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Looks up a variable keeping lists and maps intact
+    pub(crate) fn lookup_value(&self, key: &str) -> Option<Value> {
+        if key == "_" || BUILTINS.contains(&key) {
+            return self.lookup(key).map(Value::Str);
+        }
+
+        for scope in self.scope_stack.iter().rev() {
+            if let Some(value) = scope.get(key) {
+                return Some(value.clone());
+            }
+        }
+
+        self.globals.get(key).cloned()
+    }
+
+    /// Sets a variable in the innermost scope
+    pub(crate) fn declare(&mut self, key: &str, value: Value) {
+        match self.scope_stack.last_mut() {
+            Some(scope) => scope.set_value(key.to_string(), value),
+            None => {
+                self.globals.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    /// Sets the closest variable with the same name
+    /// or a global variable if none is defined
+    pub(crate) fn assign(&mut self, key: &str, value: Value) {
+        for scope in self.scope_stack.iter_mut().rev() {
+            if scope.get(key).is_some() {
+                scope.set_value(key.to_string(), value);
+                return;
+            }
+        }
+
+        self.globals.insert(key.to_string(), value);
+    }
+
+    /// Gets a global variable
+    pub(crate) fn global(&self, key: &str) -> Option<&Value> {
+        self.globals.get(key)
+    }
+
+    /// Sets a global variable regardless of any local variables
+    pub(crate) fn set_global(&mut self, key: &str, value: Value) {
+        self.globals.insert(key.to_string(), value);
+    }
+
+    /// Gets an entry of a global map
+    pub(crate) fn entry(&self, name: &str, key: &str) -> Option<&Value> {
+        match self.globals.get(name) {
+            Some(Value::Map(map)) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Sets an entry of a global map,
+    /// the global is replaced with a new map if it is not already one
+    pub(crate) fn set_entry(&mut self, name: &str, key: String, value: Value) {
+        let global = self
+            .globals
+            .entry(name.to_string())
+            .or_insert_with(|| Value::Map(BTreeMap::new()));
+
+        match global {
+            Value::Map(map) => {
+                map.insert(key, value);
+            }
+            _ => {
+                let mut map = BTreeMap::new();
+                map.insert(key, value);
+                *global = Value::Map(map);
+            }
+        }
+    }
+
+    /// Removes an entry from a global map
+    pub(crate) fn remove_entry(&mut self, name: &str, key: &str) {
+        if let Some(Value::Map(map)) = self.globals.get_mut(name) {
+            map.remove(key);
+        }
+    }
+
+    /// The keys of a global map in order
+    pub(crate) fn keys(&self, name: &str) -> Vec<String> {
+        match self.globals.get(name) {
+            Some(Value::Map(map)) => map.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Gets a procedure defined by the program
+    pub(crate) fn procedure(&self, name: &str) -> Option<&'a Procedure> {
+        self.procedures.get(name).copied()
+    }
+
+    /// Runs a procedure body with only its own scope visible,
+    /// the caller's scopes are restored afterwards
+    pub(crate) fn call<F>(&mut self, scope: Scope, body: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Self),
+    {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(format!(
+                "exceeded the maximum call depth of {}",
+                MAX_CALL_DEPTH
+            ));
+        }
+
+        let caller = self.scope_stack.split_off(self.base());
+
+        self.depth += 1;
+        self.push(scope);
+        body(self);
+        self.pop();
+        self.depth -= 1;
+
+        self.scope_stack.extend(caller);
+        Ok(())
+    }
+
+    /// The variables of the current scopes by name,
+    /// inner scopes shadow outer ones
+    pub(crate) fn scope_vars(&self) -> BTreeMap<String, Value> {
+        let mut vars = BTreeMap::new();
+
+        for scope in &self.scope_stack[self.base()..] {
+            for (key, value) in scope.vars() {
+                vars.insert(key.to_string(), value.clone());
+            }
+        }
+
+        vars
+    }
+
+    /// The environment variable scope sits beneath every other scope
+    fn base(&self) -> usize {
+        usize::from(cfg!(feature = "envvar")).min(self.scope_stack.len())
+    }
+
     pub(crate) fn push(&mut self, scope: Scope) {
         self.scope_stack.push(scope);
     }
@@ -85,6 +334,134 @@ impl<'a> Environment<'a> {
         self.scope_stack.pop();
     }
 
+    /// Reads the next record of input, counting its line number
+    pub(crate) fn read_record(&mut self) -> Option<String> {
+        let record = self.input.read()?;
+
+        self.lineno += 1;
+        self.file_lineno += 1;
+        self.look_ahead();
+
+        Some(record)
+    }
+
+    /// Sets the named inputs read one after another in a run
+    pub(crate) fn inputs<I>(&mut self, inputs: I)
+    where
+        I: Iterator<Item = (String, Records<'a>)> + 'a,
+    {
+        self.inputs = Box::new(inputs);
+    }
+
+    /// Starts reading from the next input of the run, false after the last one
+    pub(crate) fn next_input(&mut self) -> bool {
+        let (filename, input) = match self.upcoming.pop_front() {
+            Some(next) => next,
+            None => match self.inputs.next() {
+                Some(next) => next,
+                None => return false,
+            },
+        };
+
+        self.filename = filename;
+        self.file_lineno = 0;
+        self.columns.clear();
+        self.widths.clear();
+        self.input = input;
+
+        true
+    }
+
+    /// Opens the following inputs until enough records are read ahead,
+    /// so the end of the run is known when the last inputs are short
+    fn look_ahead(&mut self) {
+        while self.ahead() < self.lookahead {
+            match self.inputs.next() {
+                Some((filename, mut input)) => {
+                    input.fill();
+                    self.upcoming.push_back((filename, input));
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// The number of records read ahead of the current one,
+    /// not counting the headers of the upcoming inputs
+    fn ahead(&self) -> usize {
+        let header = usize::from(self.header);
+        let upcoming = self
+            .upcoming
+            .iter()
+            .map(|(_, input)| input.remaining().saturating_sub(header))
+            .sum::<usize>();
+
+        self.input.remaining() + upcoming
+    }
+
+    /// Reads the header of the current input, naming the columns of the
+    /// following lines, the header is not counted as a line
+    pub(crate) fn read_header(&mut self) -> Option<String> {
+        let header = self.input.read()?;
+        self.look_ahead();
+
+        if self.fixed_width {
+            let (columns, widths) = super::fixed::infer(&header).into_iter().unzip();
+            self.columns = columns;
+            self.widths = widths;
+        } else {
+            self.columns = self.fields(&header);
+        }
+
+        Some(header)
+    }
+
+    /// The fields of the current line as variables named by the header
+    pub(crate) fn columns(&self) -> Scope {
+        let mut scope = Scope::new();
+
+        if let Event::Line(line) = &self.event {
+            for (name, field) in self.columns.iter().zip(self.fields(line)) {
+                scope.set(name.to_string(), field);
+            }
+        }
+
+        scope
+    }
+
+    /// Parses the current line as JSON for paths to look into
+    #[cfg(feature = "json")]
+    pub(crate) fn parse_json(&mut self) -> Result<(), String> {
+        if let Event::Line(line) = &self.event {
+            self.json = Some(super::json::parse(line)?);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "json"))]
+    pub(crate) fn parse_json(&mut self) -> Result<(), String> {
+        Err("romulus was compiled without json support".to_string())
+    }
+
+    /// Gets a value in the current JSON line
+    #[cfg(feature = "json")]
+    pub(crate) fn path(&self, path: &[String]) -> Option<String> {
+        let json = self.json.as_ref()?;
+        super::json::lookup(json, path).map(super::json::to_string)
+    }
+
+    #[cfg(not(feature = "json"))]
+    pub(crate) fn path(&self, _path: &[String]) -> Option<String> {
+        None
+    }
+
+    /// The number of records after the current one in the run,
+    /// only exact within the program's lookahead
+    pub(crate) fn remaining(&self) -> usize {
+        self.ahead()
+    }
+
     pub(crate) fn print(&mut self, reader: &mut dyn Read) {
         let _ = copy(reader, self.out);
     }
@@ -97,14 +474,91 @@ impl<'a> Environment<'a> {
         self.quit
     }
 
+    /// Skips the rest of the program for the current event
+    pub(crate) fn skip_event(&mut self) {
+        self.skipped = true
+    }
+
+    /// Whether the current event was skipped
+    pub(crate) fn skipped(&self) -> bool {
+        self.skipped
+    }
+
+    /// Drops the current event from being implicitly printed
+    pub(crate) fn delete_event(&mut self) {
+        self.deleted = true
+    }
+
+    /// Whether the current event was deleted
+    pub(crate) fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    /// Whether no more statements should be performed for the current event
+    pub(crate) fn halted(&self) -> bool {
+        self.quit || self.skipped
+    }
+
+    /// Performs statements for the current event even when it was halted,
+    /// keeping the halt for the rest of the program
+    pub(crate) fn resume<F: FnOnce(&mut Self)>(&mut self, perform: F) {
+        let (quit, skipped) = (self.quit, self.skipped);
+        self.quit = false;
+        self.skipped = false;
+
+        perform(self);
+
+        self.quit |= quit;
+        self.skipped |= skipped;
+    }
+
+    /// Starts handling a new event
+    pub(crate) fn start_event(&mut self, event: Event) {
+        self.event = event;
+        self.skipped = false;
+        self.deleted = false;
+        self.tracker.reset();
+
+        #[cfg(feature = "json")]
+        {
+            self.json = None;
+        }
+    }
+
     pub(crate) fn split_line<F, T>(&self, handle: F) -> Option<T>
     where
-        F: Fn(&mut Split) -> T,
+        F: FnOnce(&mut dyn Iterator<Item = &str>) -> T,
     {
-        if let Event::Line(line) = &self.event {
-            Some(handle(&mut self.seperator.split(line)))
-        } else {
-            None
+        let line = match &self.event {
+            Event::Line(line) => line,
+            _ => return None,
+        };
+
+        if self.delimited.is_none() && self.widths.is_empty() {
+            return Some(handle(&mut self.seperator.split(line)));
+        }
+
+        let fields = self.fields(line);
+        Some(handle(&mut fields.iter().map(String::as_str)))
+    }
+
+    /// Reads the current line as logfmt `key=value` pairs
+    pub(crate) fn logfmt(&self) -> Option<Vec<(String, String)>> {
+        match &self.event {
+            Event::Line(line) => Some(super::logfmt::pairs(line)),
+            _ => None,
+        }
+    }
+
+    fn fields(&self, line: &str) -> Vec<String> {
+        match self.delimited {
+            Some(delimited) => delimited.split(line),
+            None if !self.widths.is_empty() => super::fixed::split(line, &self.widths),
+            None => self
+                .seperator
+                .split(line)
+                .map(ToString::to_string)
+                .collect(),
         }
     }
 
@@ -130,14 +584,20 @@ impl<'a> Environment<'a> {
 
     pub(crate) fn print_event(&mut self) {
         if let Event::Line(line) = &self.event {
-            let buf = format!("{}{}", line, nl!());
+            let buf = format!("{}{}", line, self.record_end);
             let _ = copy(&mut buf.as_bytes(), self.out);
         }
     }
 
+    /// Prints a record followed by the end of record
+    pub(crate) fn print_record(&mut self, record: &str) {
+        let buf = format!("{}{}", record, self.record_end);
+        let _ = copy(&mut buf.as_bytes(), self.out);
+    }
+
     #[cfg(feature = "bind")]
     pub(crate) fn bind_variable(&mut self, key: &str) {
-        if let Some(value) = self.lookup(key) {
+        if let Some(value) = self.lookup_value(key) {
             self.globals.insert(key.to_string(), value);
         }
     }
@@ -0,0 +1,128 @@
+//! Cuts lines into fixed width columns
+
+use crate::ast::Column;
+
+/// Cuts a column out of a line by character position and trims it,
+/// columns past the end of the line are empty
+pub(crate) fn cut(line: &str, column: Column) -> String {
+    let chars = line.chars().skip(column.start);
+
+    let field = match column.end {
+        Some(end) => chars.take(end - column.start).collect::<String>(),
+        None => chars.collect::<String>(),
+    };
+
+    field.trim().to_string()
+}
+
+/// Finds the names in a header with the columns they are written in
+pub(crate) fn infer(header: &str) -> Vec<(String, Column)> {
+    let mut names: Vec<(String, Column)> = Vec::new();
+    let mut prev = ' ';
+
+    for (pos, ch) in header.chars().enumerate() {
+        match names.last_mut() {
+            Some((name, column)) if !ch.is_whitespace() && !prev.is_whitespace() => {
+                name.push(ch);
+                column.end = Some(pos + 1);
+            }
+            _ if !ch.is_whitespace() => names.push((
+                ch.to_string(),
+                Column {
+                    start: pos,
+                    end: Some(pos + 1),
+                },
+            )),
+            _ => (),
+        }
+
+        prev = ch;
+    }
+
+    names
+}
+
+/// Splits a line into the columns named by a header
+///
+/// Values may be left or right aligned to their names, so a line is split
+/// at its last whitespace between two names, or at the first whitespace
+/// after that for a value wider than the space given to it
+pub(crate) fn split(line: &str, names: &[Column]) -> Vec<String> {
+    let chars = line.chars().collect::<Vec<char>>();
+    let blank = |pos: &usize| chars.get(*pos).is_some_and(|ch| ch.is_whitespace());
+
+    let mut fields = Vec::with_capacity(names.len());
+    let mut start = 0;
+
+    for (idx, name) in names.iter().enumerate() {
+        let end = match names.get(idx + 1) {
+            Some(next) => {
+                let gap = name.end.unwrap_or(name.start).max(start + 1);
+
+                (gap..next.start)
+                    .rev()
+                    .find(blank)
+                    .or_else(|| (next.start.max(gap)..chars.len()).find(blank))
+                    .unwrap_or(chars.len())
+            }
+            None => chars.len(),
+        };
+
+        let end = end.clamp(start, chars.len().max(start));
+        let field = chars.get(start..end).unwrap_or_default();
+
+        fields.push(field.iter().collect::<String>().trim().to_string());
+        start = end;
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(start: usize, end: Option<usize>) -> Column {
+        Column { start, end }
+    }
+
+    #[test]
+    fn cutting() {
+        let line = "root         1  /sbin/init splash";
+
+        assert_eq!(cut(line, column(0, Some(8))), "root");
+        assert_eq!(cut(line, column(8, Some(14))), "1");
+        assert_eq!(cut(line, column(14, None)), "/sbin/init splash");
+        assert_eq!(cut(line, column(40, None)), "");
+    }
+
+    #[test]
+    fn inference() {
+        assert_eq!(
+            infer("  PID TTY      CMD"),
+            vec![
+                ("PID".to_string(), column(2, Some(5))),
+                ("TTY".to_string(), column(6, Some(9))),
+                ("CMD".to_string(), column(15, Some(18))),
+            ]
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        let names = infer("USER         PID COMMAND")
+            .into_iter()
+            .map(|(_, column)| column)
+            .collect::<Vec<Column>>();
+
+        assert_eq!(
+            split("alice      12345 vim notes.txt", &names),
+            vec!["alice", "12345", "vim notes.txt"]
+        );
+        assert_eq!(
+            split("a_very_long_user 7 sh", &names),
+            vec!["a_very_long_user", "7", "sh"]
+        );
+        assert_eq!(split("root", &names), vec!["root", "", ""]);
+    }
+}
@@ -0,0 +1,84 @@
+/// What happens to a line which is not a valid JSON object when reading JSON lines,
+/// lines such as `3`, `"x"` or `[1]` are valid JSON but not objects
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InvalidJson {
+    /// The line is dropped without running the program
+    Skip,
+
+    /// The line is printed as is without running the program
+    Pass,
+
+    /// Processing stops with an error
+    Error,
+}
+
+/// Parses a line of JSON holding an object
+#[cfg(feature = "json")]
+pub(crate) fn parse(line: &str) -> Result<serde_json::Value, String> {
+    match serde_json::from_str(line).map_err(|err| err.to_string())? {
+        json @ serde_json::Value::Object(_) => Ok(json),
+        _ => Err("expected a JSON object".to_string()),
+    }
+}
+
+/// Gets the value at a path of object keys or array indices
+#[cfg(feature = "json")]
+pub(crate) fn lookup<'a>(
+    json: &'a serde_json::Value,
+    path: &[String],
+) -> Option<&'a serde_json::Value> {
+    use serde_json::Value::*;
+
+    path.iter().try_fold(json, |value, key| match value {
+        Object(map) => map.get(key),
+        Array(items) => key.parse::<usize>().ok().and_then(|idx| items.get(idx)),
+        _ => None,
+    })
+}
+
+/// Gives a JSON value as a romulus string, strings are unquoted,
+/// null is empty and arrays and objects are kept as JSON
+#[cfg(feature = "json")]
+pub(crate) fn to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    fn get(line: &str, path: &str) -> Option<String> {
+        let json = parse(line).unwrap();
+        let path = path.split('.').map(ToString::to_string).collect::<Vec<_>>();
+
+        lookup(&json, &path).map(to_string)
+    }
+
+    #[test]
+    fn paths() {
+        let line = r#"{"a": {"b": [1, "two", null]}, "c": true}"#;
+
+        assert_eq!(get(line, "a.b.0"), Some("1".to_string()));
+        assert_eq!(get(line, "a.b.1"), Some("two".to_string()));
+        assert_eq!(get(line, "a.b.2"), Some("".to_string()));
+        assert_eq!(get(line, "a.b"), Some(r#"[1,"two",null]"#.to_string()));
+        assert_eq!(get(line, "c"), Some("true".to_string()));
+        assert_eq!(get(line, "a.x"), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(parse("{\"a\": ").is_err());
+    }
+
+    #[test]
+    fn not_object() {
+        for line in &["3", "\"x\"", "[1]", "null"] {
+            assert_eq!(parse(line), Err("expected a JSON object".to_string()));
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Reads the `key=value` pairs of logfmt lines
+
+/// Splits a line into its pairs, values may be double quoted with
+/// backslash escapes and a key without a value is given an empty one
+pub(crate) fn pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+
+        if chars.peek().is_none() {
+            return pairs;
+        }
+
+        let mut key = String::new();
+        while let Some(ch) = chars.next_if(|ch| *ch != '=' && !ch.is_whitespace()) {
+            key.push(ch);
+        }
+
+        let mut value = String::new();
+
+        if chars.next_if_eq(&'=').is_some() {
+            if chars.next_if_eq(&'"').is_some() {
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('r') => value.push('\r'),
+                            Some('t') => value.push('\t'),
+                            Some(other) => value.push(other),
+                            None => value.push('\\'),
+                        },
+                        ch => value.push(ch),
+                    }
+                }
+            } else {
+                while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace()) {
+                    value.push(ch);
+                }
+            }
+        }
+
+        if !key.is_empty() {
+            pairs.push((key, value));
+        }
+    }
+}
+
+/// Gets the value of a key in a line, the first one if it is repeated
+pub(crate) fn lookup<'p>(pairs: &'p [(String, String)], key: &str) -> Option<&'p str> {
+    pairs
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(
+            pairs("level=info  msg=started took=3ms"),
+            owned(&[("level", "info"), ("msg", "started"), ("took", "3ms")])
+        );
+    }
+
+    #[test]
+    fn quoted() {
+        assert_eq!(
+            pairs(r#"msg="say \"hi\"\nbye" path="a b" empty="""#),
+            owned(&[("msg", "say \"hi\"\nbye"), ("path", "a b"), ("empty", "")])
+        );
+    }
+
+    #[test]
+    fn bare_keys() {
+        assert_eq!(
+            pairs("debug a= b=1 =x"),
+            owned(&[("debug", ""), ("a", ""), ("b", "1")])
+        );
+    }
+}
@@ -1,12 +1,26 @@
 //! A module organizing the runtime elements of a romulus program
 
+mod delimited;
+pub(crate) mod emit;
 mod environment;
+pub(crate) mod fixed;
+mod json;
+pub(crate) mod logfmt;
+mod number;
 pub(crate) mod op;
 mod range_scope_tracker;
+mod record;
 mod scope;
+mod value;
 
+pub use delimited::Delimited;
 pub use environment::{Environment, Event};
+pub use json::InvalidJson;
+pub use number::Number;
 pub(crate) use range_scope_tracker::RangeScopeTracker;
+pub use record::RecordSeparator;
+pub(crate) use record::Records;
 pub use scope::Scope;
+pub use value::Value;
 
 pub(crate) use op::*;
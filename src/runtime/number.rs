@@ -0,0 +1,173 @@
+use crate::ast::Operator;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A numeric value used in arithmetic expressions
+///
+/// Integers are kept as integers as long as possible,
+/// any operation involving a float results in a float
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    /// A whole number
+    Int(i64),
+
+    /// A floating point number
+    Float(f64),
+}
+
+impl Number {
+    /// Applies an arithmetic operator to two numbers
+    pub fn apply(self, op: Operator, other: Number) -> Result<Number, String> {
+        use Number::*;
+
+        match (self, other) {
+            (Int(lh), Int(rh)) => int_apply(op, lh, rh),
+            (lh, rh) => float_apply(op, lh.as_float(), rh.as_float()),
+        }
+    }
+
+    /// Negates the number
+    pub fn neg(self) -> Result<Number, String> {
+        match self {
+            Number::Int(num) => num
+                .checked_neg()
+                .map(Number::Int)
+                .ok_or_else(|| "integer overflow".to_string()),
+            Number::Float(num) => Ok(Number::Float(-num)),
+        }
+    }
+
+    fn as_float(self) -> f64 {
+        match self {
+            Number::Int(num) => num as f64,
+            Number::Float(num) => num,
+        }
+    }
+}
+
+fn int_apply(op: Operator, lh: i64, rh: i64) -> Result<Number, String> {
+    if rh == 0 && (op == Operator::Div || op == Operator::Mod) {
+        return Err("division by zero".to_string());
+    }
+
+    let result = match (op, lh.checked_rem(rh)) {
+        (Operator::Add, _) => lh.checked_add(rh),
+        (Operator::Sub, _) => lh.checked_sub(rh),
+        (Operator::Mul, _) => lh.checked_mul(rh),
+        (Operator::Div, Some(0)) => lh.checked_div(rh),
+        (Operator::Mod, Some(rem)) => Some(rem),
+
+        // an inexact division, or one overflowing as `i64::MIN / -1` does
+        (Operator::Div, _) | (Operator::Mod, None) => return float_apply(op, lh as f64, rh as f64),
+    };
+
+    result
+        .map(Number::Int)
+        .ok_or_else(|| "integer overflow".to_string())
+}
+
+fn float_apply(op: Operator, lh: f64, rh: f64) -> Result<Number, String> {
+    if rh == 0.0 && (op == Operator::Div || op == Operator::Mod) {
+        return Err("division by zero".to_string());
+    }
+
+    Ok(Number::Float(match op {
+        Operator::Add => lh + rh,
+        Operator::Sub => lh - rh,
+        Operator::Mul => lh * rh,
+        Operator::Div => lh / rh,
+        Operator::Mod => lh % rh,
+    }))
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(lh), Number::Int(rh)) => lh.partial_cmp(rh),
+            (lh, rh) => lh.as_float().partial_cmp(&rh.as_float()),
+        }
+    }
+}
+
+impl FromStr for Number {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Number, String> {
+        let trimmed = s.trim();
+
+        if let Ok(num) = trimmed.parse::<i64>() {
+            return Ok(Number::Int(num));
+        }
+
+        match trimmed.parse::<f64>() {
+            Ok(num) if num.is_finite() => Ok(Number::Float(num)),
+            _ => Err(format!("'{}' is not a number", s)),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Int(num) => write!(f, "{}", num),
+            Number::Float(num) => write!(f, "{}", num),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("42".parse(), Ok(Number::Int(42)));
+        assert_eq!(" -3 ".parse(), Ok(Number::Int(-3)));
+        assert_eq!("2.5".parse(), Ok(Number::Float(2.5)));
+        assert!("abc".parse::<Number>().is_err());
+        assert!("".parse::<Number>().is_err());
+        assert!("inf".parse::<Number>().is_err());
+    }
+
+    #[test]
+    fn int_arithmetic() {
+        use Number::*;
+
+        assert_eq!(Int(1).apply(Operator::Add, Int(2)), Ok(Int(3)));
+        assert_eq!(Int(1).apply(Operator::Sub, Int(2)), Ok(Int(-1)));
+        assert_eq!(Int(3).apply(Operator::Mul, Int(2)), Ok(Int(6)));
+        assert_eq!(Int(6).apply(Operator::Div, Int(2)), Ok(Int(3)));
+        assert_eq!(Int(7).apply(Operator::Div, Int(2)), Ok(Float(3.5)));
+        assert_eq!(Int(7).apply(Operator::Mod, Int(2)), Ok(Int(1)));
+    }
+
+    #[test]
+    fn mixed_arithmetic() {
+        use Number::*;
+
+        assert_eq!(Int(1).apply(Operator::Add, Float(0.5)), Ok(Float(1.5)));
+        assert_eq!(Float(1.5).apply(Operator::Mul, Int(2)), Ok(Float(3.0)));
+    }
+
+    #[test]
+    fn arithmetic_errors() {
+        use Number::*;
+
+        assert!(Int(1).apply(Operator::Div, Int(0)).is_err());
+        assert!(Int(1).apply(Operator::Mod, Int(0)).is_err());
+        assert!(Float(1.0).apply(Operator::Div, Int(0)).is_err());
+        assert!(Int(i64::MAX).apply(Operator::Add, Int(1)).is_err());
+    }
+
+    #[test]
+    fn min_by_minus_one() {
+        use Number::*;
+
+        assert_eq!(
+            Int(i64::MIN).apply(Operator::Div, Int(-1)),
+            Ok(Float(-(i64::MIN as f64)))
+        );
+        assert!(Int(i64::MIN).apply(Operator::Mod, Int(-1)).is_ok());
+    }
+}
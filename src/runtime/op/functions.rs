@@ -0,0 +1,291 @@
+use crate::runtime::{Number, Value};
+
+/// A built-in function which can be called from an expression
+///
+/// ```text
+/// print upper(name)
+/// ```
+pub struct Function {
+    /// The name the function is called by
+    pub name: &'static str,
+
+    /// The least number of arguments accepted
+    pub min_args: usize,
+
+    /// The most number of arguments accepted
+    pub max_args: usize,
+
+    call: fn(&[Value]) -> Result<Value, String>,
+}
+
+impl Function {
+    /// Calls the function with the given arguments
+    pub fn call(&self, args: &[Value]) -> Result<Value, String> {
+        if args.len() < self.min_args || args.len() > self.max_args {
+            return Err(format!(
+                "{} expects {} but received {}",
+                self.name,
+                self.arity(),
+                args.len()
+            ));
+        }
+
+        (self.call)(args)
+    }
+
+    /// A description of the number of arguments accepted
+    pub fn arity(&self) -> String {
+        if self.min_args == self.max_args {
+            format!("{} arguments", self.min_args)
+        } else {
+            format!("{} to {} arguments", self.min_args, self.max_args)
+        }
+    }
+}
+
+/// The widest pad accepted, wider pads are almost certainly a mistake
+const MAX_PAD_WIDTH: u64 = 1 << 20;
+
+const FUNCTIONS: &[Function] = &[
+    Function {
+        name: "upper",
+        min_args: 1,
+        max_args: 1,
+        call: |args| Ok(Value::Str(args[0].to_string().to_uppercase())),
+    },
+    Function {
+        name: "lower",
+        min_args: 1,
+        max_args: 1,
+        call: |args| Ok(Value::Str(args[0].to_string().to_lowercase())),
+    },
+    Function {
+        name: "len",
+        min_args: 1,
+        max_args: 1,
+        call: len,
+    },
+    Function {
+        name: "trim",
+        min_args: 1,
+        max_args: 1,
+        call: |args| Ok(Value::Str(args[0].to_string().trim().to_string())),
+    },
+    Function {
+        name: "substr",
+        min_args: 2,
+        max_args: 3,
+        call: substr,
+    },
+    Function {
+        name: "replace",
+        min_args: 3,
+        max_args: 3,
+        call: |args| {
+            let (s, from, to) = (
+                args[0].to_string(),
+                args[1].to_string(),
+                args[2].to_string(),
+            );
+            Ok(Value::Str(s.replace(&from, &to)))
+        },
+    },
+    Function {
+        name: "pad",
+        min_args: 2,
+        max_args: 3,
+        call: pad,
+    },
+    Function {
+        name: "split",
+        min_args: 1,
+        max_args: 2,
+        call: split,
+    },
+    Function {
+        name: "join",
+        min_args: 1,
+        max_args: 2,
+        call: join,
+    },
+];
+
+/// Finds a built-in function by name
+pub fn function(name: &str) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|func| func.name == name)
+}
+
+fn int_arg(args: &[Value], pos: usize) -> Result<i64, String> {
+    match args[pos].to_string().parse::<Number>()? {
+        Number::Int(num) => Ok(num),
+        Number::Float(num) => Err(format!("'{}' is not an integer", num)),
+    }
+}
+
+fn len(args: &[Value]) -> Result<Value, String> {
+    let len = match &args[0] {
+        Value::Str(s) => s.chars().count(),
+        Value::List(items) => items.len(),
+        Value::Map(map) => map.len(),
+    };
+
+    Ok(Value::Str(len.to_string()))
+}
+
+/// substr(s, start, [len]) with a zero based start,
+/// a negative start counts back from the end of the string
+fn substr(args: &[Value]) -> Result<Value, String> {
+    let chars = args[0].to_string().chars().collect::<Vec<char>>();
+    let count = chars.len() as i64;
+
+    let start = int_arg(args, 1)?;
+    let start = if start < 0 { count + start } else { start };
+    let start = start.max(0).min(count);
+
+    let end = if args.len() == 3 {
+        start.saturating_add(int_arg(args, 2)?.max(0)).min(count)
+    } else {
+        count
+    };
+
+    Ok(Value::Str(
+        chars[start as usize..end as usize].iter().collect(),
+    ))
+}
+
+/// pad(s, width, [fill]) following printf, a positive width
+/// right aligns and a negative width left aligns
+fn pad(args: &[Value]) -> Result<Value, String> {
+    let s = args[0].to_string();
+    let width = int_arg(args, 1)?;
+
+    if width.unsigned_abs() > MAX_PAD_WIDTH {
+        return Err(format!(
+            "pad width {} is wider than {}",
+            width, MAX_PAD_WIDTH
+        ));
+    }
+
+    let fill = if args.len() == 3 {
+        let fill = args[2].to_string();
+        let mut chars = fill.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => ch,
+            _ => return Err(format!("pad fill must be one character not '{}'", fill)),
+        }
+    } else {
+        ' '
+    };
+
+    let missing = (width.unsigned_abs() as usize).saturating_sub(s.chars().count());
+    let padding = fill.to_string().repeat(missing);
+
+    if width < 0 {
+        Ok(Value::Str(format!("{}{}", s, padding)))
+    } else {
+        Ok(Value::Str(format!("{}{}", padding, s)))
+    }
+}
+
+/// split(s, [sep]) splits on whitespace when no seperator is given
+fn split(args: &[Value]) -> Result<Value, String> {
+    let s = args[0].to_string();
+
+    let items = if args.len() == 2 {
+        let sep = args[1].to_string();
+
+        if sep.is_empty() {
+            return Err("split seperator must not be empty".to_string());
+        }
+
+        s.split(&sep).map(ToString::to_string).collect()
+    } else {
+        s.split_whitespace().map(ToString::to_string).collect()
+    };
+
+    Ok(Value::List(items))
+}
+
+/// join(list, [sep]) joins with a space when no seperator is given
+fn join(args: &[Value]) -> Result<Value, String> {
+    let sep = if args.len() == 2 {
+        args[1].to_string()
+    } else {
+        " ".to_string()
+    };
+
+    match &args[0] {
+        Value::List(items) => Ok(Value::Str(items.join(&sep))),
+        Value::Str(s) => Ok(Value::Str(s.clone())),
+        Value::Map(_) => Err("join expects a list but received a map".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[&str]) -> Result<String, String> {
+        let args = args
+            .iter()
+            .map(|arg| Value::Str(arg.to_string()))
+            .collect::<Vec<Value>>();
+
+        function(name).unwrap().call(&args).map(|v| v.to_string())
+    }
+
+    #[test]
+    fn case() {
+        assert_eq!(call("upper", &["abc"]), Ok("ABC".to_string()));
+        assert_eq!(call("lower", &["ABC"]), Ok("abc".to_string()));
+    }
+
+    #[test]
+    fn substr() {
+        assert_eq!(call("substr", &["hello", "1"]), Ok("ello".to_string()));
+        assert_eq!(call("substr", &["hello", "1", "3"]), Ok("ell".to_string()));
+        assert_eq!(call("substr", &["hello", "-2"]), Ok("lo".to_string()));
+        assert_eq!(call("substr", &["hello", "9"]), Ok("".to_string()));
+        assert_eq!(
+            call("substr", &["hello", "1", "9223372036854775807"]),
+            Ok("ello".to_string())
+        );
+        assert!(call("substr", &["hello", "x"]).is_err());
+    }
+
+    #[test]
+    fn pad() {
+        assert_eq!(call("pad", &["ab", "4"]), Ok("  ab".to_string()));
+        assert_eq!(call("pad", &["ab", "-4", "."]), Ok("ab..".to_string()));
+        assert_eq!(call("pad", &["abcde", "2"]), Ok("abcde".to_string()));
+        assert!(call("pad", &["ab", "9223372036854775807"]).is_err());
+        assert!(call("pad", &["ab", "-9223372036854775808"]).is_err());
+    }
+
+    #[test]
+    fn split_and_join() {
+        let parts = function("split")
+            .unwrap()
+            .call(&[Value::Str("a,b,c".to_string()), Value::Str(",".to_string())])
+            .unwrap();
+
+        assert_eq!(
+            function("join")
+                .unwrap()
+                .call(&[parts.clone(), Value::Str("-".to_string())]),
+            Ok(Value::Str("a-b-c".to_string()))
+        );
+
+        assert_eq!(
+            function("len").unwrap().call(&[parts]),
+            Ok(Value::Str("3".to_string()))
+        );
+    }
+
+    #[test]
+    fn arity() {
+        assert!(call("upper", &[]).is_err());
+        assert!(call("upper", &["a", "b"]).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ast::{Body, Match, Selector};
+use crate::ast::{Arm, Body, Match, Selector};
 use crate::runtime::Event;
 
 pub(crate) trait Lifecycle {
@@ -7,16 +7,25 @@ pub(crate) trait Lifecycle {
 
 impl Lifecycle for Event {
     fn is_lifecycle(&self) -> bool {
-        matches!(self, Event::Begin | Event::End)
+        matches!(
+            self,
+            Event::Begin | Event::End | Event::BeginFile | Event::EndFile
+        )
     }
 }
 
 impl Lifecycle for Body {
     fn is_lifecycle(&self) -> bool {
         match self {
-            Body::Bare(_) => false,
+            Body::Bare(_) | Body::Def(_) => false,
             Body::Single(sel, _) => sel.is_lifecycle(),
             Body::Guard(sel, _) => sel.is_lifecycle(),
+            Body::Branch(sel, _, _) => sel.is_lifecycle(),
+            Body::Switch(arms, default) => {
+                default.is_none()
+                    && !arms.is_empty()
+                    && arms.iter().all(|Arm(sel, _)| sel.is_lifecycle())
+            }
         }
     }
 }
@@ -32,6 +41,9 @@ impl Lifecycle for Selector {
 
 impl Lifecycle for Match {
     fn is_lifecycle(&self) -> bool {
-        matches!(self, Match::Begin | Match::End)
+        matches!(
+            self,
+            Match::Begin | Match::End | Match::BeginFile | Match::EndFile
+        )
     }
 }
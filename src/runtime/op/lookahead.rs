@@ -0,0 +1,99 @@
+use crate::ast;
+
+//
+// Specifies how many records after the current one need to be read ahead
+// to know if a line is one of the last lines, such as `-1` or `last`
+//
+pub(crate) trait Lookahead {
+    fn lookahead(&self) -> usize;
+}
+
+impl Lookahead for ast::Seq {
+    fn lookahead(&self) -> usize {
+        self.subnodes
+            .iter()
+            .map(Lookahead::lookahead)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Lookahead for ast::Body {
+    fn lookahead(&self) -> usize {
+        use ast::Body::*;
+
+        match self {
+            Bare(stmt) => stmt.lookahead(),
+            Single(sel, stmt) => sel.lookahead().max(stmt.lookahead()),
+            Guard(sel, seq) => sel.lookahead().max(seq.lookahead()),
+            Branch(sel, then, otherwise) => sel
+                .lookahead()
+                .max(then.lookahead())
+                .max(otherwise.lookahead()),
+            Switch(arms, default) => arms
+                .iter()
+                .map(|ast::Arm(sel, seq)| sel.lookahead().max(seq.lookahead()))
+                .chain(default.iter().map(Lookahead::lookahead))
+                .max()
+                .unwrap_or(0),
+            Def(procedure) => procedure.body.lookahead(),
+        }
+    }
+}
+
+impl Lookahead for ast::Statement {
+    fn lookahead(&self) -> usize {
+        match self {
+            ast::Statement::For(_, _, seq) => seq.lookahead(),
+            _ => 0,
+        }
+    }
+}
+
+impl Lookahead for ast::Selector {
+    fn lookahead(&self) -> usize {
+        use ast::Selector::*;
+
+        match self {
+            Match(m) => m.lookahead(),
+            Range(range) => range.lookahead(),
+            Pattern(_) | Logfmt(_) | Columns(_) | Contains(_, _) | Compare(_, _) => 0,
+            Negate(sub) => sub.lookahead(),
+            Conjunction(lh, rh) | Disjunction(lh, rh) => lh.lookahead().max(rh.lookahead()),
+        }
+    }
+}
+
+impl Lookahead for ast::Range {
+    fn lookahead(&self) -> usize {
+        self.start
+            .lookahead()
+            .max(self.end.lookahead())
+            .max(self.enter.lookahead())
+            .max(self.exit.lookahead())
+    }
+}
+
+impl<T: Lookahead> Lookahead for Option<T> {
+    fn lookahead(&self) -> usize {
+        self.as_ref().map_or(0, Lookahead::lookahead)
+    }
+}
+
+impl Lookahead for ast::RangeEnd {
+    fn lookahead(&self) -> usize {
+        match self {
+            ast::RangeEnd::Match(m) => m.lookahead(),
+            ast::RangeEnd::Count(_) | ast::RangeEnd::Multiple(_) => 0,
+        }
+    }
+}
+
+impl Lookahead for ast::Match {
+    fn lookahead(&self) -> usize {
+        match self {
+            ast::Match::Index(idx) if *idx < 0 => idx.unsigned_abs() as usize,
+            _ => 0,
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use super::{Environment, Event, Scope};
+use super::{fixed, logfmt, Environment, Event, Scope};
 
+mod functions;
 mod lifecycle;
+mod lookahead;
 mod operation;
 mod range_cap;
 mod scope_persister;
@@ -9,11 +11,13 @@ mod selector;
 mod sig_statement;
 mod valuable;
 
+pub use functions::*;
 pub use operation::*;
 pub use scope_provider::*;
 pub use selector::*;
 pub use valuable::*;
 
+pub(crate) use lookahead::*;
 pub(crate) use range_cap::*;
 pub(crate) use scope_persister::*;
 pub(crate) use sig_statement::*;
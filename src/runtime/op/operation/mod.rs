@@ -15,41 +15,115 @@ pub trait Operation {
 
 impl Operation for ast::Seq {
     fn perform(&self, env: &mut Environment) {
-        if env.finished() {
+        if env.halted() {
             return;
         }
 
+        // the ranges of a block only see the lines which reach the block
+        if !self.toplevel && matches!(env.event, Event::Line(_)) {
+            self.persist_scope(env);
+        }
+
+        env.push(Scope::new());
+
         for sub in &self.subnodes {
             if !self.toplevel || env.event.is_lifecycle() == sub.is_lifecycle() {
                 sub.perform(env)
+            } else {
+                env.tracker.skip(sub.num_ranges());
             }
         }
+
+        env.pop();
     }
 }
 
 impl Operation for ast::Body {
     fn perform(&self, env: &mut Environment) {
         use ast::Body::*;
-        if env.finished() {
+        if env.halted() {
             return;
         }
 
         match self {
             Bare(func_node) => func_node.perform(env),
             Single(sel_node, node) => {
-                if sel_node.select(env) {
-                    env.push(sel_node.scope(env));
+                if let Some(scope) = selected(sel_node, env) {
+                    env.push(scope);
                     node.perform(env);
                     env.pop();
                 }
             }
             Guard(sel_node, node) => {
-                if sel_node.select(env) {
-                    env.push(sel_node.scope(env));
+                let start = env.tracker.position();
+
+                if let Some(scope) = selected(sel_node, env) {
+                    env.push(scope);
                     node.perform(env);
                     env.pop();
+
+                    close_range(sel_node, start, env);
+                } else {
+                    env.tracker.skip(node.num_ranges());
+                }
+            }
+            Branch(sel_node, then, otherwise) => {
+                let start = env.tracker.position();
+
+                if let Some(scope) = selected(sel_node, env) {
+                    env.push(scope);
+                    then.perform(env);
+                    env.pop();
+
+                    close_range(sel_node, start, env);
+
+                    env.tracker.skip(otherwise.num_ranges());
+                } else {
+                    env.tracker.skip(then.num_ranges());
+
+                    otherwise.perform(env);
                 }
             }
+            Switch(arms, default) => switch(arms, default, env),
+            Def(_) => (),
+        }
+    }
+}
+
+/// Selects the current event, giving the variables of the selector when it matches
+fn selected(sel_node: &ast::Selector, env: &mut Environment) -> Option<Scope> {
+    let start = env.tracker.position();
+
+    if sel_node.select(env) {
+        env.tracker.seek(start);
+        Some(sel_node.scope(env))
+    } else {
+        None
+    }
+}
+
+fn switch(arms: &[ast::Arm], default: &Option<ast::Seq>, env: &mut Environment) {
+    let mut matched = false;
+
+    for ast::Arm(sel_node, node) in arms {
+        if matched {
+            env.tracker.skip(sel_node.num_ranges() + node.num_ranges());
+        } else if let Some(scope) = selected(sel_node, env) {
+            env.push(scope);
+            node.perform(env);
+            env.pop();
+
+            matched = true;
+        } else {
+            env.tracker.skip(node.num_ranges());
+        }
+    }
+
+    if let Some(node) = default {
+        if matched {
+            env.tracker.skip(node.num_ranges());
+        } else {
+            node.perform(env);
         }
     }
 }
@@ -58,13 +132,17 @@ impl Operation for ast::Statement {
     fn perform(&self, env: &mut Environment) {
         use ast::Statement::*;
 
-        if env.finished() {
+        if env.halted() {
             return;
         }
 
         match self {
             Print(expr) => print(expr, env),
             Quit => quit(env),
+            Next => next(env),
+            Delete => delete(env),
+            Hold(op) => hold(*op, env),
+            Pull => pull(env),
             Subst(regex, expr) => subst(regex, expr, env),
             Gsubst(regex, expr) => gsubst(regex, expr, env),
             Read(expr) => read(expr, env),
@@ -72,6 +150,13 @@ impl Operation for ast::Statement {
             Exec(expr) => exec(expr, env),
             Append(expr) => append(expr, env),
             Set(expr) => set(expr, env),
+            Let(id, expr) => declare(id, expr, env),
+            Assign(id, expr) => assign(id, expr, env),
+            Accumulate(acc, target, expr) => accumulate(*acc, target, expr, env),
+            DeleteEntry(name, key) => delete_entry(name, key, env),
+            For(var, map, seq) => for_each(var, map, seq, env),
+            Call(name, args) => call(name, args, env),
+            Emit(format, names) => emit(*format, names, env),
 
             #[cfg(feature = "bind")]
             Bind(id) => bind(id, env),
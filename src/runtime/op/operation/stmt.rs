@@ -1,6 +1,7 @@
-use super::Valuable;
 use super::*;
+use super::{Evaluate, Valuable};
 use crate::ast::*;
+use crate::runtime::{emit, Number, Value};
 use ansi_term::Colour::Red;
 use regex::Regex;
 use Event::*;
@@ -15,13 +16,49 @@ macro_rules! error {
 }
 
 pub fn print(expr: &Expression, env: &mut Environment) {
-    env.print(&mut format!("{}{}", expr.to_value(env), nl!()).as_bytes());
+    let value = expr.to_value(env);
+    env.print_record(&value);
 }
 
 pub fn quit(env: &mut Environment) {
     env.quit()
 }
 
+pub fn next(env: &mut Environment) {
+    env.skip_event()
+}
+
+pub fn delete(env: &mut Environment) {
+    env.delete_event()
+}
+
+pub fn hold(op: HoldSpace, env: &mut Environment) {
+    env.replace_line(|env, line| match op {
+        HoldSpace::Hold => {
+            env.hold = line.clone();
+            line
+        }
+        HoldSpace::HoldAppend => {
+            env.hold.push('\n');
+            env.hold.push_str(&line);
+            line
+        }
+        HoldSpace::Fetch => env.hold.clone(),
+        HoldSpace::FetchAppend => format!("{}\n{}", line, env.hold),
+        HoldSpace::Swap => std::mem::replace(&mut env.hold, line),
+    })
+}
+
+pub fn pull(env: &mut Environment) {
+    if !matches!(env.event, Line(_)) {
+        return;
+    }
+
+    if let Some(next) = env.read_record() {
+        env.replace_line(|_, line| format!("{}\n{}", line, next));
+    }
+}
+
 pub fn subst(regex: &Regex, expr: &Expression, env: &mut Environment) {
     env.replace_line(|env, line| {
         regex
@@ -54,7 +91,6 @@ pub fn read(expr: &Expression, env: &mut Environment) {
 pub fn write(expr: &Expression, env: &mut Environment) {
     if let Line(line) = &env.event {
         let mut file = match std::fs::OpenOptions::new()
-            .write(true)
             .append(true)
             .create(true)
             .open(expr.to_value(env))
@@ -63,7 +99,7 @@ pub fn write(expr: &Expression, env: &mut Environment) {
             Err(msg) => error!("Error opening file {}", msg),
         };
 
-        match write!(file, "{}{}", line, nl!()) {
+        match write!(file, "{}{}", line, env.record_end) {
             Ok(_) => (),
             Err(msg) => error!("Error writing to file {}", msg),
         }
@@ -84,13 +120,152 @@ pub fn exec(expr: &Expression, env: &mut Environment) {
 }
 
 pub fn append(expr: &Expression, env: &mut Environment) {
-    env.replace_line(|env, line| format!("{}{}", line, expr.to_value(&env)));
+    env.replace_line(|env, line| format!("{}{}", line, expr.to_value(env)));
 }
 
 pub fn set(expr: &Expression, env: &mut Environment) {
     env.replace_line(|env, _| expr.to_value(env))
 }
 
+pub fn declare(id: &str, expr: &Expression, env: &mut Environment) {
+    let value = expr.evaluate(env);
+    env.declare(id, value);
+}
+
+pub fn assign(target: &Target, expr: &Expression, env: &mut Environment) {
+    let value = expr.evaluate(env);
+
+    match target {
+        Target::Variable(id) => env.assign(id, value),
+        Target::Entry(id, key) => {
+            let key = key.to_value(env);
+            env.set_entry(id, key, value);
+        }
+    }
+}
+
+pub fn accumulate(acc: Accumulator, target: &Target, expr: &Expression, env: &mut Environment) {
+    let value = expr.to_value(env);
+
+    let (name, key) = match target {
+        Target::Variable(id) => (id, None),
+        Target::Entry(id, key) => (id, Some(key.to_value(env))),
+    };
+
+    let current = match &key {
+        None => env.global(name),
+        Some(key) => env.entry(name, key),
+    };
+
+    let result = match combine(acc, current.cloned(), value) {
+        Ok(result) => result,
+        Err(msg) => error!("unable to accumulate {}: {}", name, msg),
+    };
+
+    match key {
+        None => env.set_global(name, result),
+        Some(key) => env.set_entry(name, key, result),
+    }
+}
+
+fn combine(acc: Accumulator, current: Option<Value>, value: String) -> Result<Value, String> {
+    if acc == Accumulator::Push {
+        let mut items = match current {
+            Some(Value::List(items)) => items,
+            Some(other) => vec![other.to_string()],
+            None => vec![],
+        };
+
+        items.push(value);
+        return Ok(Value::List(items));
+    }
+
+    let num = value.parse::<Number>()?;
+    let current = match current {
+        Some(current) => Some(current.to_string().parse::<Number>()?),
+        None => None,
+    };
+
+    let result = match (acc, current) {
+        (_, None) => num,
+        (Accumulator::Sum, Some(current)) => current.apply(Operator::Add, num)?,
+        (Accumulator::Min, Some(current)) if num < current => num,
+        (Accumulator::Max, Some(current)) if num > current => num,
+        (_, Some(current)) => current,
+    };
+
+    Ok(Value::Str(result.to_string()))
+}
+
+pub fn delete_entry(name: &str, key: &Expression, env: &mut Environment) {
+    let key = key.to_value(env);
+    env.remove_entry(name, &key);
+}
+
+pub fn for_each(var: &str, map: &str, seq: &Seq, env: &mut Environment) {
+    for key in env.keys(map) {
+        let mut scope = Scope::new();
+        scope.set(var.to_string(), key);
+
+        env.push(scope);
+        seq.perform(env);
+        env.pop();
+
+        if env.halted() {
+            return;
+        }
+    }
+}
+
+pub fn emit(format: Format, names: &Option<Vec<String>>, env: &mut Environment) {
+    let fields = match names {
+        Some(names) => names
+            .iter()
+            .map(|name| (name.to_string(), env.lookup_value(name).unwrap_or_default()))
+            .collect::<Vec<(String, Value)>>(),
+        None => env.scope_vars().into_iter().collect(),
+    };
+
+    if format == Format::Csv {
+        let names = fields.iter().map(|(name, _)| name.to_string());
+        let names = names.collect::<Vec<String>>();
+
+        // each set of columns gets its own header before its first row
+        if !env.csv_headers.contains(&names) {
+            env.print_record(&emit::header(&names));
+            env.csv_headers.insert(names);
+        }
+    }
+
+    let record = emit::record(format, &fields);
+    env.print_record(&record);
+}
+
+pub fn call(name: &str, args: &[Expression], env: &mut Environment) {
+    let procedure = match env.procedure(name) {
+        Some(procedure) => procedure,
+        None => error!("unknown procedure {}", name),
+    };
+
+    if args.len() != procedure.params.len() {
+        error!(
+            "procedure {} expects {} arguments but received {}",
+            name,
+            procedure.params.len(),
+            args.len()
+        );
+    }
+
+    let mut scope = Scope::new();
+    for (param, arg) in procedure.params.iter().zip(args) {
+        scope.set_value(param.to_string(), arg.evaluate(env));
+    }
+
+    if let Err(msg) = env.call(scope, |env| procedure.body.perform(env)) {
+        error!("procedure {} {}", name, msg);
+    }
+}
+
 #[cfg(feature = "bind")]
 pub fn bind(id: &str, env: &mut Environment) {
     env.bind_variable(id);
@@ -25,6 +25,18 @@ impl RangeCap for ast::Body {
             Bare(_) => 0,
             Single(sel, _) => sel.num_ranges(),
             Guard(sel, node) => sel.num_ranges() + node.num_ranges(),
+            Branch(sel, then, otherwise) => {
+                sel.num_ranges() + then.num_ranges() + otherwise.num_ranges()
+            }
+            Switch(arms, default) => {
+                let arms: usize = arms
+                    .iter()
+                    .map(|ast::Arm(sel, seq)| sel.num_ranges() + seq.num_ranges())
+                    .sum();
+
+                arms + default.as_ref().map_or(0, RangeCap::num_ranges)
+            }
+            Def(_) => 0,
         }
     }
 }
@@ -37,6 +49,10 @@ impl RangeCap for ast::Selector {
             Match(_) => 0,
             Range(_) => 1,
             Pattern(_) => 0,
+            Logfmt(_) => 0,
+            Columns(_) => 0,
+            Contains(_, _) => 0,
+            Compare(_, _) => 0,
             Negate(sub) => sub.num_ranges(),
             Conjunction(lh, rh) => lh.num_ranges() + rh.num_ranges(),
             Disjunction(lh, rh) => lh.num_ranges() + rh.num_ranges(),
@@ -1,6 +1,11 @@
 use super::*;
 use crate::ast;
 
+/// Opens and closes ranges for the current event
+///
+/// A block persists the ranges of the bodies directly inside of it,
+/// moving past the ranges of nested blocks which are persisted
+/// when the nested block is performed
 pub trait ScopePersister {
     fn persist_scope(&self, env: &mut Environment);
 }
@@ -10,7 +15,10 @@ impl ScopePersister for ast::Selector {
         use ast::Selector::*;
 
         match self {
-            Range(r) => r.persist_scope(env),
+            Range(r) => {
+                r.persist_scope(env);
+                env.tracker.next();
+            }
             Negate(s) => s.persist_scope(env),
             Disjunction(lh, rh) => {
                 lh.persist_scope(env);
@@ -29,9 +37,13 @@ impl ScopePersister for ast::Selector {
 
 impl ScopePersister for ast::Seq {
     fn persist_scope(&self, env: &mut Environment) {
+        let start = env.tracker.position();
+
         for node in &self.subnodes {
             node.persist_scope(env);
         }
+
+        env.tracker.seek(start);
     }
 }
 
@@ -39,27 +51,129 @@ impl ScopePersister for ast::Body {
     fn persist_scope(&self, env: &mut Environment) {
         use ast::Body::*;
         match self {
-            Bare(_) => (),
+            Bare(_) | Def(_) => (),
             Single(s, _) => s.persist_scope(env),
-            Guard(s, _) => s.persist_scope(env),
+            Guard(s, seq) => {
+                s.persist_scope(env);
+                nested(seq, env);
+            }
+            Branch(s, then, otherwise) => {
+                s.persist_scope(env);
+                nested(then, env);
+                nested(otherwise, env);
+            }
+            Switch(arms, default) => {
+                for ast::Arm(s, seq) in arms {
+                    s.persist_scope(env);
+                    nested(seq, env);
+                }
+
+                if let Some(seq) = default {
+                    nested(seq, env);
+                }
+            }
         }
     }
 }
 
+/// Moves past the ranges of a nested block, except when the
+/// input ends which closes every range that is still open
+fn nested(seq: &ast::Seq, env: &mut Environment) {
+    if env.event == Event::End {
+        seq.persist_scope(env);
+    }
+
+    env.tracker.skip(seq.num_ranges());
+}
+
 impl ScopePersister for ast::Range {
     fn persist_scope(&self, env: &mut Environment) {
-        let ast::Range(start, end) = self;
+        let ast::Range {
+            start,
+            end,
+            inclusive,
+            enter,
+            exit,
+        } = self;
 
-        if !env.tracker.in_range() {
-            if start.select(env) {
-                env.tracker.set(start.scope(env));
+        // the input ending closes every open range, but the range
+        // stays active for the end event
+        if env.event == Event::End {
+            if env.tracker.in_range() {
+                hook(exit, env);
             }
-        } else if end.select(env) {
+
+            return;
+        }
+
+        if env.tracker.closing() {
+            hook(exit, env);
             env.tracker.clear();
+        }
+
+        if !env.tracker.in_range() {
+            open(start, enter, env);
+        } else if ends(end, env) {
+            if *inclusive {
+                let scope = match end {
+                    ast::RangeEnd::Match(m) => m.scope(env),
+                    _ => Scope::default(),
+                };
 
-            if start.select(env) {
-                env.tracker.set(start.scope(env));
+                env.tracker.close(scope);
+            } else {
+                hook(exit, env);
+                env.tracker.clear();
+                open(start, enter, env);
             }
         };
     }
 }
+
+/// Closes an inclusive range after its block ran on the last line of the range,
+/// running the exit block even when the block quit or skipped the line
+pub(crate) fn close_range(sel: &ast::Selector, start: usize, env: &mut Environment) {
+    if let ast::Selector::Range(range) = sel {
+        let pos = env.tracker.position();
+        env.tracker.seek(start);
+
+        if env.tracker.closing() {
+            env.resume(|env| hook(&range.exit, env));
+            env.tracker.clear();
+        }
+
+        env.tracker.seek(pos);
+    }
+}
+
+/// Starts the range if the current line matches its start
+fn open(start: &ast::Match, enter: &Option<ast::Seq>, env: &mut Environment) {
+    if start.select(env) {
+        let scope = start.scope(env);
+        env.tracker.set(scope, env.lineno);
+        hook(enter, env);
+    }
+}
+
+/// Performs an enter or exit block with the scope of the current range
+fn hook(block: &Option<ast::Seq>, env: &mut Environment) {
+    if let (Some(block), Some(scope)) = (block, env.tracker.get()) {
+        env.push(scope.clone());
+        block.perform(env);
+        env.pop();
+    }
+}
+
+/// Whether the current line is past the end of the current range
+fn ends(end: &ast::RangeEnd, env: &mut Environment) -> bool {
+    let started = env.tracker.started().unwrap_or_default();
+
+    match end {
+        ast::RangeEnd::Match(m) => m.select(env),
+        ast::RangeEnd::Count(count) => env.lineno > started + count,
+        ast::RangeEnd::Multiple(multiple) => {
+            let last = env.lineno - 1;
+            last >= started && (*multiple <= 0 || last % multiple == 0)
+        }
+    }
+}
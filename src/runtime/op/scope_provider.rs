@@ -3,42 +3,62 @@ use crate::ast;
 
 use crate::lint::vars::provider::ScopeProvider as VarProvider;
 
+/// Gives the variables a selector captures, this passes over the
+/// ranges of the selector in the same way as selecting does
 pub trait ScopeProvider {
-    fn scope(&self, env: &Environment) -> Scope;
+    fn scope(&self, env: &mut Environment) -> Scope;
 }
 
 impl ScopeProvider for ast::Selector {
-    fn scope(&self, env: &Environment) -> Scope {
+    fn scope(&self, env: &mut Environment) -> Scope {
         use ast::Selector::*;
         match self {
             Match(match_node) => match_node.scope(env),
             Range(range_node) => range_node.scope(env),
             Pattern(pattern_match_node) => pattern_match_node.scope(env),
-            Negate(_) => Scope::default(),
-            Conjunction(lh, rh) => lh.scope(env) + rh.scope(env),
+            Logfmt(logfmt_match_node) => logfmt_match_node.scope(env),
+            Columns(column_match_node) => column_match_node.scope(env),
+            Negate(sub) => {
+                env.tracker.skip(sub.num_ranges());
+                Scope::default()
+            }
+            Contains(_, _) => Scope::default(),
+            Compare(expr, ast::Comparison::Matches(rgx)) => {
+                Scope::from_regex(rgx, &expr.to_value(env))
+            }
+            Compare(_, _) => Scope::default(),
+            Conjunction(lh, rh) => {
+                let scope = lh.scope(env);
+
+                env.push(scope.clone());
+                let rh_scope = rh.scope(env);
+                env.pop();
+
+                scope + rh_scope
+            }
             Disjunction(lh, rh) => (lh.scope(env) + rh.scope(env)).pick(&self.provides()),
         }
     }
 }
 
 impl ScopeProvider for ast::Range {
-    fn scope(&self, env: &Environment) -> Scope {
-        if let Some(scope) = env.tracker.get() {
-            scope.clone()
-        } else {
-            Scope::new()
-        }
+    fn scope(&self, env: &mut Environment) -> Scope {
+        let scope = env.tracker.get().cloned().unwrap_or_default();
+        env.tracker.next();
+        scope
     }
 }
 
 impl ScopeProvider for ast::Match {
-    fn scope(&self, env: &Environment) -> Scope {
+    fn scope(&self, env: &mut Environment) -> Scope {
         let mut scope = Scope::new();
 
         match self {
             ast::Match::Begin => (),
             ast::Match::End => (),
+            ast::Match::BeginFile | ast::Match::EndFile => (),
             ast::Match::Index(_) => (),
+            ast::Match::Step(_, _) => (),
             ast::Match::Regex(rgx) => {
                 if let Event::Line(line) = &env.event {
                     scope += Scope::from_regex(rgx, line)
@@ -51,7 +71,7 @@ impl ScopeProvider for ast::Match {
 }
 
 impl ScopeProvider for ast::PatternMatch {
-    fn scope(&self, env: &Environment) -> Scope {
+    fn scope(&self, env: &mut Environment) -> Scope {
         env.split_line(|parts| {
             let mut scope = Scope::new();
 
@@ -74,3 +94,52 @@ impl ScopeProvider for ast::PatternMatch {
         .unwrap_or_default()
     }
 }
+
+impl ScopeProvider for ast::LogfmtMatch {
+    fn scope(&self, env: &mut Environment) -> Scope {
+        let pairs = env.logfmt().unwrap_or_default();
+        let mut scope = Scope::new();
+
+        for (key, pattern) in &self.pairs {
+            let value = match logfmt::lookup(&pairs, key) {
+                Some(value) => value,
+                None => return Scope::new(),
+            };
+
+            scope += pattern_scope(pattern, value);
+        }
+
+        scope
+    }
+}
+
+impl ScopeProvider for ast::ColumnMatch {
+    fn scope(&self, env: &mut Environment) -> Scope {
+        let line = match &env.event {
+            Event::Line(line) => line,
+            _ => return Scope::new(),
+        };
+
+        let mut scope = Scope::new();
+
+        for (column, pattern) in &self.columns {
+            scope += pattern_scope(pattern, &fixed::cut(line, *column));
+        }
+
+        scope
+    }
+}
+
+/// The variables a pattern of a logfmt or column match binds from its value
+fn pattern_scope(pattern: &ast::Pattern, value: &str) -> Scope {
+    let mut scope = Scope::new();
+
+    match pattern {
+        ast::Pattern::Identifier(id) if id == "_" => (),
+        ast::Pattern::Identifier(id) => scope.set(id.clone(), value.to_string()),
+        ast::Pattern::String(_, _) => (),
+        ast::Pattern::Regex(rgx) => scope += Scope::from_regex(rgx, value),
+    }
+
+    scope
+}
@@ -1,5 +1,7 @@
 use super::*;
 use crate::ast;
+use crate::runtime::Number;
+use std::cmp::Ordering;
 
 pub trait Selector {
     fn select(&self, env: &mut Environment) -> bool;
@@ -13,6 +15,8 @@ impl Selector for ast::Selector {
             Match(match_node) => match_node.select(env),
             Range(range_node) => range_node.select(env),
             Pattern(pattern_node) => pattern_node.select(env),
+            Logfmt(logfmt_node) => logfmt_node.select(env),
+            Columns(column_node) => column_node.select(env),
             Negate(selector) => !selector.select(env),
             Disjunction(lh, rh) => {
                 if lh.select(env) {
@@ -23,17 +27,67 @@ impl Selector for ast::Selector {
                 }
             }
             Conjunction(lh, rh) => {
+                let start = env.tracker.position();
+
                 if lh.select(env) {
-                    rh.select(env)
+                    // variables from the left are visible to comparisons on the right
+                    env.tracker.seek(start);
+                    let scope = lh.scope(env);
+
+                    env.push(scope);
+                    let selected = rh.select(env);
+                    env.pop();
+
+                    selected
                 } else {
                     env.tracker.skip(rh.num_ranges());
                     false
                 }
             }
+            Contains(key, map) => {
+                let key = key.to_value(env);
+                env.entry(map, &key).is_some()
+            }
+            Compare(expr, comparison) => compare(expr, comparison, env),
         }
     }
 }
 
+fn compare(expr: &ast::Expression, comparison: &ast::Comparison, env: &Environment) -> bool {
+    use ast::Comparison::*;
+
+    let value = expr.to_value(env);
+    let order = |other: &ast::Expression| {
+        let other = other.to_value(env);
+
+        match (value.parse::<Number>(), other.parse::<Number>()) {
+            (Ok(lh), Ok(rh)) => lh.partial_cmp(&rh),
+            (Err(_), Err(_)) => Some(value.as_str().cmp(&other)),
+            // a number is never ordered against text
+            _ => None,
+        }
+    };
+    let equal = |other: &ast::Expression| {
+        let other = other.to_value(env);
+
+        match (value.parse::<Number>(), other.parse::<Number>()) {
+            (Ok(lh), Ok(rh)) => lh.partial_cmp(&rh) == Some(Ordering::Equal),
+            _ => value == other,
+        }
+    };
+
+    match comparison {
+        Equal(other) => equal(other),
+        NotEqual(other) => !equal(other),
+        Less(other) => order(other) == Some(Ordering::Less),
+        LessEqual(other) => matches!(order(other), Some(Ordering::Less | Ordering::Equal)),
+        Greater(other) => order(other) == Some(Ordering::Greater),
+        GreaterEqual(other) => matches!(order(other), Some(Ordering::Greater | Ordering::Equal)),
+        Matches(rgx) => rgx.is_match(&value),
+        NotMatches(rgx) => !rgx.is_match(&value),
+    }
+}
+
 impl Selector for ast::Range {
     fn select(&self, env: &mut Environment) -> bool {
         let next = env.tracker.in_range();
@@ -49,7 +103,24 @@ impl Selector for ast::Match {
         match self {
             Begin => env.event == Event::Begin,
             End => env.event == Event::End,
+            BeginFile => env.event == Event::BeginFile,
+            EndFile => env.event == Event::EndFile,
+            Index(idx) if *idx < 0 => {
+                matches!(env.event, Event::Line(_))
+                    && env.remaining() == idx.unsigned_abs() as usize - 1
+            }
             Index(idx) => env.lineno == *idx,
+            Step(first, step) => {
+                let lineno = env.lineno;
+
+                matches!(env.event, Event::Line(_))
+                    && lineno >= *first
+                    && if *step <= 0 {
+                        lineno == *first
+                    } else {
+                        (lineno - first) % step == 0
+                    }
+            }
             Regex(rgx) => {
                 if let Event::Line(line) = &env.event {
                     rgx.is_match(line)
@@ -75,7 +146,7 @@ impl Selector for ast::PatternMatch {
                 match pattern {
                     Regex(regex) if !regex.is_match(part) => return false,
                     String(s, false) if s != part => return false,
-                    String(s, true) if interpolate(&s, env) != part => return false,
+                    String(s, true) if interpolate(s, env) != part => return false,
 
                     Identifier(_) => continue,
                     _ => continue,
@@ -87,3 +158,55 @@ impl Selector for ast::PatternMatch {
         .unwrap_or(false)
     }
 }
+
+impl Selector for ast::LogfmtMatch {
+    fn select(&self, env: &mut Environment) -> bool {
+        let pairs = match env.logfmt() {
+            Some(pairs) => pairs,
+            None => return false,
+        };
+
+        for (key, pattern) in &self.pairs {
+            let value = match logfmt::lookup(&pairs, key) {
+                Some(value) => value,
+                None => return false,
+            };
+
+            if !pattern_matches(pattern, value, env) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Selector for ast::ColumnMatch {
+    fn select(&self, env: &mut Environment) -> bool {
+        let line = match &env.event {
+            Event::Line(line) => line.clone(),
+            _ => return false,
+        };
+
+        for (column, pattern) in &self.columns {
+            if !pattern_matches(pattern, &fixed::cut(&line, *column), env) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Whether a value matches the pattern given for it by a logfmt or column match,
+/// an identifier matches any value
+fn pattern_matches(pattern: &ast::Pattern, value: &str, env: &Environment) -> bool {
+    use ast::Pattern::*;
+
+    match pattern {
+        Regex(regex) => regex.is_match(value),
+        String(s, false) => s == value,
+        String(s, true) => interpolate(s, env) == value,
+        Identifier(_) => true,
+    }
+}
@@ -34,7 +34,24 @@ impl SigStatement for ast::Body {
         match self {
             Bare(s) => s.significant(),
             Single(_, s) => s.significant(),
-            Guard(_, s) => s.significant(),
+            Guard(sel, s) => sel.significant() || s.significant(),
+            Branch(sel, then, otherwise) => {
+                sel.significant() || then.significant() || otherwise.significant()
+            }
+            Switch(arms, default) => {
+                arms.iter().any(|ast::Arm(_, seq)| seq.significant())
+                    || default.as_ref().is_some_and(SigStatement::significant)
+            }
+            Def(procedure) => procedure.body.significant(),
+        }
+    }
+}
+
+impl SigStatement for ast::Selector {
+    fn significant(&self) -> bool {
+        match self {
+            ast::Selector::Range(range) => range.hooks().any(SigStatement::significant),
+            _ => false,
         }
     }
 }
@@ -43,6 +60,9 @@ impl SigStatement for ast::Statement {
     fn significant(&self) -> bool {
         use ast::Statement::*;
 
-        matches!(self, Print(_) | Read(_) | Write(_) | Exec(_))
+        match self {
+            For(_, _, seq) => seq.significant(),
+            _ => matches!(self, Print(_) | Read(_) | Write(_) | Exec(_) | Emit(_, _)),
+        }
     }
 }
@@ -1,13 +1,29 @@
 use super::*;
 use crate::ast::*;
+use crate::runtime::{Number, Value};
+use ansi_term::Colour::Red;
 use regex::{Captures, Regex};
 
 pub trait Valuable {
     fn to_value(&self, env: &Environment) -> String;
 }
 
+/// Evaluates an expression keeping lists and maps intact
+pub trait Evaluate {
+    fn evaluate(&self, env: &Environment) -> Value;
+}
+
+/// Evaluates an expression as a number
+///
+/// Strings and variables are parsed, anything which is not
+/// an integer or a float is an error, except for unset map entries which are 0
+pub trait Numeric {
+    fn to_number(&self, env: &Environment) -> Result<Number, String>;
+}
+
 lazy_static! {
-    static ref INTERPOLATOR: Regex = Regex::new(r"\$\{(?P<name>[a-zA-Z0-9_]+)\}").unwrap();
+    static ref INTERPOLATOR: Regex =
+        Regex::new(r"\$\{(\.(?P<path>[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*)|(?P<name>[a-zA-Z0-9_]+)(\[(?P<key>[a-zA-Z0-9_]+)\])?)\}").unwrap();
 }
 
 // Not really a good other place for this imo
@@ -15,7 +31,13 @@ pub fn interpolated_variables(s: &str) -> Vec<String> {
     let mut names = Vec::new();
 
     for caps in INTERPOLATOR.captures_iter(s) {
-        names.push(caps["name"].to_string())
+        if let Some(name) = caps.name("name") {
+            names.push(name.as_str().to_string());
+        }
+
+        if let Some(key) = caps.name("key") {
+            names.push(key.as_str().to_string());
+        }
     }
 
     names
@@ -32,13 +54,88 @@ impl Valuable for Expression {
                 }
             }
 
-            Expression::Identifier(name) => {
-                if let Some(value) = env.lookup(name) {
-                    value
-                } else {
+            Expression::Identifier(name) => env.lookup(name).unwrap_or_default(),
+
+            Expression::Index(name, key) => {
+                let key = key.to_value(env);
+                env.entry(name, &key)
+                    .map(ToString::to_string)
+                    .unwrap_or_default()
+            }
+
+            Expression::Integer(num) => num.to_string(),
+            Expression::Float(num) => Number::Float(*num).to_string(),
+
+            Expression::Neg(_) | Expression::Arithmetic(_, _, _) => match self.to_number(env) {
+                Ok(num) => num.to_string(),
+                Err(msg) => {
+                    let message = format!("arithmetic error: {}", msg);
+                    eprint!("{}{}", color!(Red, message), nl!());
                     String::new()
                 }
+            },
+
+            Expression::Call(_, _) => self.evaluate(env).to_string(),
+
+            Expression::Path(path) => env.path(path).unwrap_or_default(),
+        }
+    }
+}
+
+impl Evaluate for Expression {
+    fn evaluate(&self, env: &Environment) -> Value {
+        match self {
+            Expression::Identifier(name) => env.lookup_value(name).unwrap_or_default(),
+
+            Expression::Index(name, key) => {
+                let key = key.to_value(env);
+                env.entry(name, &key).cloned().unwrap_or_default()
+            }
+
+            Expression::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(env))
+                    .collect::<Vec<Value>>();
+
+                let result = match function(name) {
+                    Some(func) => func.call(&args),
+                    None => Err(format!("unknown function {}", name)),
+                };
+
+                match result {
+                    Ok(value) => value,
+                    Err(msg) => {
+                        let message = format!("function error: {}", msg);
+                        eprint!("{}{}", color!(Red, message), nl!());
+                        Value::default()
+                    }
+                }
             }
+
+            _ => Value::Str(self.to_value(env)),
+        }
+    }
+}
+
+impl Numeric for Expression {
+    fn to_number(&self, env: &Environment) -> Result<Number, String> {
+        match self {
+            Expression::Integer(num) => Ok(Number::Int(*num)),
+            Expression::Float(num) => Ok(Number::Float(*num)),
+            Expression::Neg(sub) => sub.to_number(env)?.neg(),
+            Expression::Arithmetic(op, lh, rh) => lh.to_number(env)?.apply(*op, rh.to_number(env)?),
+
+            // an entry which is not set yet counts from 0, as in `count[k] = count[k] + 1`
+            Expression::Index(name, key) => match env.entry(name, &key.to_value(env)) {
+                Some(value) => value.to_string().parse(),
+                None => Ok(Number::Int(0)),
+            },
+
+            Expression::String(_, _)
+            | Expression::Identifier(_)
+            | Expression::Call(_, _)
+            | Expression::Path(_) => self.to_value(env).parse(),
         }
     }
 }
@@ -46,14 +143,25 @@ impl Valuable for Expression {
 pub fn interpolate(content: &str, env: &Environment) -> String {
     let intermediary = content.replace("\\$", "\0");
     let eval = |capture: &Captures| -> String {
-        let key = String::from(&capture["name"]);
-        env.lookup(&key).unwrap_or_default()
+        if let Some(path) = capture.name("path") {
+            let path = path.as_str().split('.').map(ToString::to_string);
+            return env.path(&path.collect::<Vec<String>>()).unwrap_or_default();
+        }
+
+        let name = &capture["name"];
+
+        match capture.name("key") {
+            Some(key) => {
+                let key = env.lookup(key.as_str()).unwrap_or_default();
+                env.entry(name, &key)
+                    .map(ToString::to_string)
+                    .unwrap_or_default()
+            }
+            None => env.lookup(name).unwrap_or_default(),
+        }
     };
 
-    let evaled = INTERPOLATOR
-        .replace_all(&intermediary, eval)
-        .to_owned()
-        .to_string();
+    let evaled = INTERPOLATOR.replace_all(&intermediary, eval).to_string();
 
     evaled.replace('\0', "$")
 }
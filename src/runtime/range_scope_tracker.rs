@@ -2,10 +2,23 @@ use super::Scope;
 
 #[derive(Debug)]
 pub(crate) struct RangeScopeTracker {
-    states: Vec<Option<Scope>>,
+    /// The state of each range which is active
+    states: Vec<Option<Active>>,
     pos: usize,
 }
 
+/// An open range
+#[derive(Debug, Clone)]
+struct Active {
+    scope: Scope,
+
+    /// The line the range started on
+    started: i64,
+
+    /// Whether the current line is the last line of an inclusive range
+    closing: bool,
+}
+
 impl RangeScopeTracker {
     pub(crate) fn new(cap: usize) -> RangeScopeTracker {
         RangeScopeTracker {
@@ -18,6 +31,21 @@ impl RangeScopeTracker {
         self.states[self.pos].is_some()
     }
 
+    /// Moves back to the first range for a new event,
+    /// since an event may stop before passing every range
+    pub(crate) fn reset(&mut self) {
+        self.pos = 0;
+    }
+
+    /// The current range, for coming back to it with `seek`
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub(crate) fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
     pub(crate) fn next(&mut self) {
         self.skip(1)
     }
@@ -28,12 +56,37 @@ impl RangeScopeTracker {
         }
     }
 
-    pub(crate) fn get(&self) -> &Option<Scope> {
-        &self.states[self.pos]
+    pub(crate) fn get(&self) -> Option<&Scope> {
+        self.states[self.pos].as_ref().map(|active| &active.scope)
+    }
+
+    /// The line number the current range started on
+    pub(crate) fn started(&self) -> Option<i64> {
+        self.states[self.pos].as_ref().map(|active| active.started)
+    }
+
+    /// Whether the current range ended on the previous line
+    pub(crate) fn closing(&self) -> bool {
+        self.states[self.pos]
+            .as_ref()
+            .is_some_and(|active| active.closing)
+    }
+
+    pub(crate) fn set(&mut self, scope: Scope, lineno: i64) {
+        self.states[self.pos] = Some(Active {
+            scope,
+            started: lineno,
+            closing: false,
+        });
     }
 
-    pub(crate) fn set(&mut self, scope: Scope) {
-        self.states[self.pos] = Some(scope);
+    /// Keeps the current range open for its last line
+    /// with the captures of the end added to its scope
+    pub(crate) fn close(&mut self, scope: Scope) {
+        if let Some(active) = &mut self.states[self.pos] {
+            active.scope += scope;
+            active.closing = true;
+        }
     }
 
     pub(crate) fn clear(&mut self) {
@@ -0,0 +1,442 @@
+use super::Delimited;
+use regex::Regex;
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+/// Seperates the input into the records given to a program
+#[derive(Clone, Debug, Default)]
+pub enum RecordSeparator {
+    /// Each line is a record, ending in `\n` or `\r\n`
+    #[default]
+    Newline,
+
+    /// Records end in a NUL byte, like the output of `find -print0`
+    Nul,
+
+    /// Records are seperated by one or more blank lines
+    Paragraph,
+
+    /// Records are seperated by a literal string
+    Literal(String),
+
+    /// Records are seperated by matches of a regex
+    Pattern(Regex),
+}
+
+/// Reads records from an input stream
+pub(crate) struct Records<'a> {
+    input: Box<dyn BufRead + 'a>,
+    sep: RecordSeparator,
+    pending: String,
+    eof: bool,
+
+    /// Where in pending the search for the next seperator resumes
+    scanned: usize,
+
+    /// The number of bytes read from the input
+    consumed: usize,
+
+    /// The byte offset of the last record read
+    offset: usize,
+
+    /// Records read before they are needed, with their offsets
+    ahead: VecDeque<(usize, String)>,
+    lookahead: usize,
+
+    /// The delimited format whose quoted fields may contain newlines
+    quoted: Option<Delimited>,
+}
+
+impl<'a> Records<'a> {
+    pub(crate) fn new<R: BufRead + 'a>(input: R, sep: RecordSeparator) -> Records<'a> {
+        Records {
+            input: Box::new(input),
+            sep,
+            pending: String::new(),
+            eof: false,
+            scanned: 0,
+            consumed: 0,
+            offset: 0,
+            ahead: VecDeque::new(),
+            lookahead: 0,
+            quoted: None,
+        }
+    }
+
+    /// Keeps lines together while a double quoted field is open,
+    /// for delimited input with embedded newlines
+    pub(crate) fn quoted(mut self, quoted: Option<Delimited>) -> Records<'a> {
+        self.quoted = quoted;
+        self
+    }
+
+    /// Keeps up to the given number of records read ahead
+    /// so that the number of records remaining is known near the end
+    pub(crate) fn lookahead(mut self, lookahead: usize) -> Records<'a> {
+        self.lookahead = lookahead;
+        self
+    }
+
+    /// Reads the next record without its seperator
+    pub(crate) fn read(&mut self) -> Option<String> {
+        let record = match self.ahead.pop_front() {
+            Some(record) => Some(record),
+            None => self.next(),
+        };
+
+        if let Some((offset, _)) = record {
+            self.offset = offset;
+        }
+
+        self.fill();
+
+        record.map(|(_, record)| record)
+    }
+
+    /// Reads ahead up to the lookahead without taking a record
+    pub(crate) fn fill(&mut self) {
+        while self.ahead.len() < self.lookahead {
+            match self.next() {
+                Some(next) => self.ahead.push_back(next),
+                None => break,
+            }
+        }
+    }
+
+    /// The byte offset in the input where the last record read starts
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The number of records after the last one read,
+    /// this is only exact when it is less than the lookahead
+    pub(crate) fn remaining(&self) -> usize {
+        self.ahead.len()
+    }
+
+    fn next(&mut self) -> Option<(usize, String)> {
+        let start = self.consumed;
+
+        match self.sep {
+            RecordSeparator::Newline => match self.quoted {
+                Some(delimited) => self.quoted_line(delimited).map(|line| (start, line)),
+                None => self.line().map(|line| (start, trim_line_ending(line))),
+            },
+            RecordSeparator::Nul => self.nul().map(|record| (start, record)),
+            RecordSeparator::Paragraph => self.paragraph(),
+            RecordSeparator::Literal(_) | RecordSeparator::Pattern(_) => self.split(),
+        }
+    }
+
+    fn line(&mut self) -> Option<String> {
+        let mut line = String::new();
+
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(read) => {
+                self.consumed += read;
+                Some(line)
+            }
+        }
+    }
+
+    fn quoted_line(&mut self, delimited: Delimited) -> Option<String> {
+        let mut record = self.line()?;
+        let mut quoted = delimited.unterminated(&record, false);
+
+        // only the new line is scanned, carrying the quote over from the last
+        while quoted {
+            match self.line() {
+                Some(line) => {
+                    quoted = delimited.unterminated(&line, true);
+                    record.push_str(&line);
+                }
+                None => break,
+            }
+        }
+
+        Some(trim_line_ending(record))
+    }
+
+    fn nul(&mut self) -> Option<String> {
+        let mut buf = Vec::new();
+
+        match self.input.read_until(b'\0', &mut buf) {
+            Ok(0) | Err(_) => None,
+            Ok(read) => {
+                self.consumed += read;
+
+                if buf.last() == Some(&b'\0') {
+                    buf.pop();
+                }
+
+                String::from_utf8(buf).ok()
+            }
+        }
+    }
+
+    fn paragraph(&mut self) -> Option<(usize, String)> {
+        let mut lines = Vec::new();
+        let mut start = self.consumed;
+
+        while let Some(line) = self.line() {
+            let line = trim_line_ending(line);
+
+            match (line.is_empty(), lines.is_empty()) {
+                (true, true) => start = self.consumed,
+                (true, false) => break,
+                (false, _) => lines.push(line),
+            }
+        }
+
+        if lines.is_empty() {
+            None
+        } else {
+            Some((start, lines.join("\n")))
+        }
+    }
+
+    fn split(&mut self) -> Option<(usize, String)> {
+        loop {
+            // everything pending has been read but not yet returned
+            let offset = self.consumed - self.pending.len();
+
+            match self.find() {
+                // a match at the end of what is read so far may continue in the next read
+                Some((start, end)) if end < self.pending.len() || self.eof => {
+                    let record = self.pending[..start].to_string();
+                    self.pending.drain(..end);
+                    self.scanned = 0;
+
+                    return Some((offset, record));
+                }
+                Some((start, _)) => self.scanned = start,
+                None => self.scanned = self.resume(),
+            }
+
+            if self.eof {
+                let rest = trim_line_ending(std::mem::take(&mut self.pending));
+                self.scanned = 0;
+
+                return if rest.is_empty() {
+                    None
+                } else {
+                    Some((offset, rest))
+                };
+            }
+
+            match self.line() {
+                Some(line) => self.pending.push_str(&line),
+                None => self.eof = true,
+            }
+        }
+    }
+
+    fn find(&self) -> Option<(usize, usize)> {
+        match &self.sep {
+            RecordSeparator::Literal(lit) if !lit.is_empty() => self.pending[self.scanned..]
+                .find(lit.as_str())
+                .map(|start| (self.scanned + start, self.scanned + start + lit.len())),
+
+            RecordSeparator::Pattern(regex) => {
+                let mut from = self.scanned;
+
+                while let Some(m) = regex.find_at(&self.pending, from) {
+                    if m.start() != m.end() {
+                        return Some((m.start(), m.end()));
+                    }
+
+                    // empty matches do not seperate anything
+                    from = m.end() + self.pending[m.end()..].chars().next()?.len_utf8();
+                }
+
+                None
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Where the search resumes after no seperator was found in pending,
+    /// so that a literal is not searched for again with each line read
+    fn resume(&self) -> usize {
+        match &self.sep {
+            // only the end of pending may hold the start of a literal
+            RecordSeparator::Literal(lit) => {
+                let mut resume = self
+                    .pending
+                    .len()
+                    .saturating_sub(lit.len().saturating_sub(1));
+
+                while !self.pending.is_char_boundary(resume) {
+                    resume -= 1;
+                }
+
+                resume
+            }
+
+            // a regex match may start anywhere in pending
+            _ => 0,
+        }
+    }
+}
+
+fn trim_line_ending(mut line: String) -> String {
+    if line.ends_with('\n') {
+        line.pop();
+
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(input: &str, sep: RecordSeparator) -> Vec<String> {
+        let mut input = input.as_bytes();
+        let mut records = Records::new(&mut input, sep);
+        let mut result = Vec::new();
+
+        while let Some(record) = records.read() {
+            result.push(record);
+        }
+
+        result
+    }
+
+    #[test]
+    fn lookahead() {
+        let mut input = "a\nb\nc\n".as_bytes();
+        let mut records = Records::new(&mut input, RecordSeparator::Newline).lookahead(2);
+        let mut remaining = Vec::new();
+
+        while records.read().is_some() {
+            remaining.push(records.remaining());
+        }
+
+        assert_eq!(remaining, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn offsets() {
+        let mut input = "ab\n\n\ncd\ne\n".as_bytes();
+        let mut records = Records::new(&mut input, RecordSeparator::Paragraph);
+        let mut offsets = Vec::new();
+
+        while records.read().is_some() {
+            offsets.push(records.offset());
+        }
+
+        assert_eq!(offsets, vec![0, 5]);
+    }
+
+    #[test]
+    fn newline() {
+        assert_eq!(
+            records("a\nb\r\n\nc", RecordSeparator::Newline),
+            vec!["a", "b", "", "c"]
+        );
+    }
+
+    #[test]
+    fn quoted() {
+        let mut input = "a,\"b\nc\"\nd\n".as_bytes();
+        let mut records =
+            Records::new(&mut input, RecordSeparator::Newline).quoted(Some(Delimited::Csv));
+
+        assert_eq!(records.read(), Some("a,\"b\nc\"".to_string()));
+        assert_eq!(records.read(), Some("d".to_string()));
+    }
+
+    #[test]
+    fn stray_quote() {
+        let mut input = "tv,55\" screen\nradio,2\n".as_bytes();
+        let mut records =
+            Records::new(&mut input, RecordSeparator::Newline).quoted(Some(Delimited::Csv));
+
+        assert_eq!(records.read(), Some("tv,55\" screen".to_string()));
+        assert_eq!(records.read(), Some("radio,2".to_string()));
+    }
+
+    #[test]
+    fn nul() {
+        assert_eq!(
+            records("a b\0c\nd\0", RecordSeparator::Nul),
+            vec!["a b", "c\nd"]
+        );
+    }
+
+    #[test]
+    fn paragraph() {
+        assert_eq!(
+            records("\n\na: 1\nb: 2\n\n\n\nc: 3\n", RecordSeparator::Paragraph),
+            vec!["a: 1\nb: 2", "c: 3"]
+        );
+    }
+
+    #[test]
+    fn literal() {
+        assert_eq!(
+            records("a;b;;c;\n", RecordSeparator::Literal(";".to_string())),
+            vec!["a", "b", "", "c"]
+        );
+    }
+
+    #[test]
+    fn literal_across_lines() {
+        assert_eq!(
+            records(
+                "a\n--\nb\nc\n--\n\n--\nd",
+                RecordSeparator::Literal("\n--\n".to_string())
+            ),
+            vec!["a", "b\nc", "", "d"]
+        );
+    }
+
+    #[test]
+    fn pattern_across_lines() {
+        let sep = RecordSeparator::Pattern(Regex::new("\n=+\n-+\n").unwrap());
+
+        assert_eq!(
+            records("a\nb\n==\n--\nc\n===\n-\nd\n", sep),
+            vec!["a\nb", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn no_trailing_seperator() {
+        assert_eq!(
+            records("abcdef\n", RecordSeparator::Literal("XYZ".to_string())),
+            vec!["abcdef"]
+        );
+        assert_eq!(
+            records("aXYZb\nc\n", RecordSeparator::Literal("XYZ".to_string())),
+            vec!["a", "b\nc"]
+        );
+
+        let sep = RecordSeparator::Pattern(Regex::new("\n--\n").unwrap());
+        assert_eq!(records("a\n--\nb\nc\nd\n", sep), vec!["a", "b\nc\nd"]);
+    }
+
+    #[test]
+    fn pattern_many_lines() {
+        let sep = RecordSeparator::Pattern(Regex::new("\n{4}").unwrap());
+
+        assert_eq!(records("a\n\n\n\nb\n", sep), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn pattern() {
+        let sep = RecordSeparator::Pattern(Regex::new("\n-+\n").unwrap());
+
+        assert_eq!(
+            records("a\n---\nb\nc\n--\nd\n", sep),
+            vec!["a", "b\nc", "d"]
+        );
+    }
+}
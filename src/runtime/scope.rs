@@ -1,3 +1,4 @@
+use super::Value;
 use regex::{Captures, Regex};
 use std::collections::HashMap;
 use std::ops::{Add, AddAssign};
@@ -5,7 +6,7 @@ use std::ops::{Add, AddAssign};
 /// A scope containing variables
 #[derive(Clone, Default, Debug)]
 pub struct Scope {
-    local: HashMap<String, String>,
+    local: HashMap<String, Value>,
 }
 
 impl Scope {
@@ -56,13 +57,22 @@ impl Scope {
 impl Scope {
     /// Sets a variable in the current scope
     pub fn set(&mut self, name: String, value: String) {
+        self.local.insert(name, Value::Str(value));
+    }
+
+    /// Sets a variable in the current scope to any value
+    pub fn set_value(&mut self, name: String, value: Value) {
         self.local.insert(name, value);
     }
 
     /// Get a variable from the current scope
-    pub fn get(&self, name: &str) -> Option<&String> {
+    pub fn get(&self, name: &str) -> Option<&Value> {
         self.local.get(name)
     }
+
+    pub(crate) fn vars(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.local.iter()
+    }
 }
 
 impl AddAssign for Scope {
@@ -87,7 +97,9 @@ impl Add for Scope {
 impl Scope {
     /// Generate a scope from os environmental variables
     pub fn env() -> Scope {
-        let local = std::env::vars().collect::<HashMap<String, String>>();
+        let local = std::env::vars()
+            .map(|(key, value)| (key, Value::Str(value)))
+            .collect::<HashMap<String, Value>>();
 
         Scope { local }
     }
@@ -98,7 +110,7 @@ impl Scope {
 
         for key in keys {
             if let Some(value) = self.local.get(key) {
-                scope.set(key.to_owned(), value.to_owned());
+                scope.set_value(key.to_owned(), value.to_owned());
             }
         }
 
@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A value held by a global variable
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A plain string, numbers are kept as strings as well
+    Str(String),
+
+    /// A list of strings built up by push
+    List(Vec<String>),
+
+    /// An associative array, kept sorted by key
+    Map(BTreeMap<String, Value>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "{}", s),
+
+            // joined with a space so the default seperator splits it back apart
+            Value::List(items) => write!(f, "{}", items.join(" ")),
+
+            Value::Map(map) => {
+                let pairs = map
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<String>>();
+
+                write!(f, "{}", pairs.join(" "))
+            }
+        }
+    }
+}
+
+impl Default for Value {
+    fn default() -> Value {
+        Value::Str(String::new())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::Str(s)
+    }
+}
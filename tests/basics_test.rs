@@ -202,3 +202,619 @@ fn selector_disjunction() {
         "this thing\nthing something there\nthere\n"
     );
 }
+
+#[test]
+fn arithmetic() {
+    check_output!(
+        "/port (?P<port>[0-9]+)/ print port + 1\n/ratio (?P<n>[0-9]+)/ print (n * 100) / 8",
+        "port 8080\nratio 3\n",
+        "8081\n37.5\n"
+    );
+}
+
+#[test]
+fn arithmetic_not_a_number() {
+    check_output!(
+        "/port (?P<port>.+)/ print port + 1",
+        "port http\nport 22\n",
+        "\n23\n"
+    );
+}
+
+#[test]
+fn let_is_block_local() {
+    check_output!(
+        "/(?P<word>[a-z]+)/ { let upper = \"<${word}>\" print upper }\nprint \"[${upper}]\"",
+        "abc\n",
+        "<abc>\n[]\n"
+    );
+}
+
+#[test]
+fn assignment_persists_across_lines() {
+    check_output!(
+        "/^host (?P<host>.*)/ last = host\n/^req (?P<path>.*)/ print \"${last}${path}\"",
+        "host a.com\nreq /x\nreq /y\nhost b.com\nreq /z\n",
+        "a.com/x\na.com/y\nb.com/z\n"
+    );
+}
+
+#[test]
+fn assignment_updates_closest_variable() {
+    check_output!(
+        "^ total = 0\n/(?P<n>[0-9]+)/ { let x = n; x = x * 2; total = total + x }\n$ print total",
+        "1\n2\n3\n",
+        "12\n"
+    );
+}
+
+#[test]
+fn accumulators() {
+    check_output!(
+        "/ERROR/ incr errors\n/took (?P<ms>[0-9.]+)ms/ { sum total, ms; min fastest, ms; max slowest, ms }\n/user=(?P<user>[a-z]+)/ push users, user\n$ print \"${errors} ${total} ${fastest} ${slowest} ${users}\"",
+        "ERROR user=bob took 12ms\nINFO user=amy took 3.5ms\nERROR took 20ms\n",
+        "2 35.5 3.5 20 bob amy\n"
+    );
+}
+
+#[test]
+fn accumulators_skip_non_numbers() {
+    check_output!(
+        "[_, n] sum total, n\n$ print total",
+        "a 1\nb x\nc 2\n",
+        "3\n"
+    );
+}
+
+#[test]
+fn map_group_by() {
+    check_output!(
+        "[ip, _, code] { incr count[ip]; last[ip] = code }\n$ { for ip in count { print \"${ip} ${count[ip]} ${last[ip]}\" } }",
+        "10.0.0.2 GET 200\n10.0.0.1 GET 404\n10.0.0.2 POST 500\n",
+        "10.0.0.1 1 404\n10.0.0.2 2 500\n"
+    );
+}
+
+#[test]
+fn map_unset_entry_arithmetic() {
+    check_output!(
+        "[ip] count[ip] = count[ip] + 1\n$ { for ip in count { print \"${ip} ${count[ip]}\" } }",
+        "a\nb\na\n",
+        "a 2\nb 1\n"
+    );
+}
+
+#[test]
+fn map_contains_and_delete() {
+    check_output!(
+        "/open (?P<id>[0-9]+)/ open[id] = _\n/close (?P<id>[0-9]+)/ { id in open print \"closed ${open[id]}\"; delete open[id] }\n$ { for id in open { print \"leaked ${id}\" } }",
+        "open 1\nopen 2\nclose 1\nclose 1\n",
+        "closed open 1\nleaked 2\n"
+    );
+}
+
+#[test]
+fn string_functions() {
+    check_output!(
+        "[name, path] { let n = pad(upper(name), -5); let parts = split(path, '/'); let dotted = join(parts, '.'); print \"${n}|${dotted}\" print len(parts) print substr(name, 1) print replace(path, '/', ':') }",
+        "bob a/b/c\n",
+        "BOB  |a.b.c\n3\nob\na:b:c\n"
+    );
+}
+
+#[test]
+fn else_branch() {
+    check_output!(
+        "/user=(?P<user>[a-z]+)/ { print \"user ${user}\" } else { print 'anonymous' }",
+        "user=bob\nhello\n",
+        "user bob\nanonymous\n"
+    );
+}
+
+#[test]
+fn else_chain() {
+    check_output!(
+        "/ERROR/ print 'e' else /WARN/ print 'w' else print 'i'",
+        "ERROR x\nWARN y\nINFO z\n",
+        "e\nw\ni\n"
+    );
+}
+
+#[test]
+fn else_range() {
+    check_output!(
+        "/b/,/d/ print _ else print '-'",
+        "a\nb\nc\nd\ne\n",
+        "-\nb\nc\n-\n-\n"
+    );
+}
+
+#[test]
+fn switch_first_arm_wins() {
+    check_output!(
+        "match {\n  /ERROR/ => print 'e',\n  [_, level] => { print level }\n  _ => print '?'\n}",
+        "ERROR x\nWARN y\nINFO\n",
+        "e\ny\n?\n"
+    );
+}
+
+#[test]
+fn switch_range() {
+    check_output!(
+        "match { /b/,/d/ => print _, _ => print '-' }",
+        "a\nb\nc\nd\ne\n",
+        "-\nb\nc\n-\n-\n"
+    );
+}
+
+#[test]
+fn switch_lifecycle() {
+    check_output!(
+        "match { ^ => print 'begin', $ => print 'end' }\n/a/ print 'A'",
+        "a\nb\n",
+        "begin\nA\nend\n"
+    );
+}
+
+#[test]
+fn procedure_call() {
+    check_output!(
+        "def show(label, value) {\n  print \"${label}: ${value}\"\n}\n[name, size] show(name, size)",
+        "a 1\nb 2\n",
+        "a: 1\nb: 2\n"
+    );
+}
+
+#[test]
+fn procedure_recursion() {
+    check_output!(
+        "def count(n) {\n  n > 0 { print n; count(n - 1) }\n}\n^ count(3)",
+        "",
+        "3\n2\n1\n"
+    );
+}
+
+#[test]
+fn procedure_recursion_limit() {
+    check_output!("def f() { f() }\n^ f()\n$ print 'done'", "", "done\n");
+}
+
+#[test]
+fn procedure_scope() {
+    check_output!(
+        "^ total = 0\ndef add(n) { total = total + n }\n[_, size] { let n = 'shadowed'; add(size) }\n$ print total",
+        "a 1\nb 2\n",
+        "3\n"
+    );
+}
+
+#[test]
+fn next_skips_rest() {
+    check_output!("/^#/ next\nsubst /a/, 'b'", "#a\nab\n#c\n", "#a\nbb\n#c\n");
+}
+
+#[test]
+fn next_drops_print() {
+    let interpreter = romulus::Interpreter::builder()
+        .expression("/^#/ next\nsubst /a/, 'b'".to_string())
+        .print_next(false)
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    interpreter.process(&mut "#a\nab\n#c\n".as_bytes(), &mut out);
+
+    assert_eq!(String::from_utf8(out).unwrap(), "bb\n");
+}
+
+#[test]
+fn next_keeps_range() {
+    check_output!(
+        "/x/ next\n/b/,/d/ subst /^/, '> '",
+        "a\nb\nx\nc\nd\ne\n",
+        "a\n> b\nx\n> c\nd\ne\n"
+    );
+}
+
+#[test]
+fn next_in_procedure() {
+    check_output!(
+        "def skip(line) { line == 'b' next }\nskip(_)\nprint _",
+        "a\nb\nc\n",
+        "a\nc\n"
+    );
+}
+
+#[test]
+fn delete_line() {
+    check_output!(
+        "/^DEBUG/ delete\nsubst /a/, 'b'",
+        "INFO a\nDEBUG a\nWARN a\n",
+        "INFO b\nWARN b\n"
+    );
+}
+
+#[test]
+fn delete_keeps_running() {
+    check_output!(
+        "/^DEBUG/ delete\n/^DEBUG/ incr count\n$ print count",
+        "DEBUG a\nINFO b\nDEBUG c\n",
+        "2\n"
+    );
+}
+
+#[test]
+fn hold_and_fetch() {
+    check_output!(
+        "/1|3/ { hold; delete }\n/2|4/ fetch_append",
+        "1\n2\n3\n4\n",
+        "2\n1\n4\n3\n"
+    );
+}
+
+#[test]
+fn hold_append_and_fetch() {
+    check_output!(
+        "!/END/ { hold_append; delete }\n/END/ fetch",
+        "a\nb\nEND\n",
+        "\na\nb\n"
+    );
+}
+
+#[test]
+fn swap_hold() {
+    check_output!("swap", "a\nb\nc\n", "\na\nb\n");
+}
+
+#[test]
+fn pull_next_line() {
+    check_output!(
+        "/,$/ { pull; subst /\\n/, ' ' }",
+        "a,\nb\nc,\nd,\ne\n",
+        "a, b\nc, d,\ne\n"
+    );
+}
+
+#[test]
+fn pull_at_end() {
+    check_output!("/,$/ pull", "a\nb,\n", "a\nb,\n");
+}
+
+#[test]
+fn paragraph_records() {
+    let interpreter = romulus::Interpreter::builder()
+        .expression(
+            "/dn: (?P<dn>[a-z]+)\\n(.*\\n)*mail: (?P<mail>.*)/ print \"${dn} ${mail}\"".to_string(),
+        )
+        .record_sep(romulus::RecordSeparator::Paragraph)
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    let mut sin = "dn: a\ncn: x\nmail: a@x\n\ndn: b\nmail: b@y\n".as_bytes();
+    interpreter.process(&mut sin, &mut out);
+
+    assert_eq!(String::from_utf8(out).unwrap(), "a a@x\nb b@y\n");
+}
+
+#[test]
+fn nul_records() {
+    let cases = vec![
+        ("/b/ delete", "a\0c\nd\0"),
+        ("print \"<${_}>\"", "<a>\0<b>\0<c\nd>\0"),
+    ];
+
+    for (prog, expected) in cases {
+        let interpreter = romulus::Interpreter::builder()
+            .expression(prog.to_string())
+            .record_sep(romulus::RecordSeparator::Nul)
+            .build()
+            .unwrap();
+
+        let mut out = Vec::new();
+        let mut sin = "a\0b\0c\nd\0".as_bytes();
+        interpreter.process(&mut sin, &mut out);
+
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
+
+#[test]
+fn empty_record_sep() {
+    assert!(romulus::Interpreter::builder()
+        .expression("print _".to_string())
+        .record_sep(romulus::RecordSeparator::Literal(String::new()))
+        .build()
+        .is_err());
+}
+
+#[test]
+fn step_address() {
+    check_output!("0~4 print _", "1\n2\n3\n4\n5\n6\n7\n8\n9\n", "4\n8\n");
+    check_output!("2~3 print _", "1\n2\n3\n4\n5\n6\n7\n8\n9\n", "2\n5\n8\n");
+}
+
+#[test]
+fn range_count() {
+    check_output!(
+        "/^header/,+2 print _",
+        "a\nheader\nb\nc\nd\nheader\ne\n",
+        "header\nb\nc\nheader\ne\n"
+    );
+}
+
+#[test]
+fn range_multiple() {
+    check_output!(
+        "/^5/,~4 print _",
+        "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n",
+        "5\n6\n7\n8\n"
+    );
+    check_output!("/^9/,~3 print _", "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n", "9\n");
+}
+
+#[test]
+fn overlapping_ranges() {
+    check_output!(
+        "/b/,/d/ subst /^/, '1'\n/c/,/e/ subst /^/, '2'",
+        "a\nb\nc\nd\ne\nf\n",
+        "a\n1b\n21c\n2d\ne\nf\n"
+    );
+    check_output!(
+        "/^(?P<x>[ab])/,/^d/ { /^(?P<y>[bc])/,/^e/ print \"${x}${y}\" }",
+        "a\nb\nc\nd\ne\n",
+        "ab\nab\n"
+    );
+}
+
+#[test]
+fn nested_ranges() {
+    check_output!(
+        "/^(?P<iface>\\w+):/,/^$/ {\n  /^  stats/,/^  end/ {\n    /rx (?P<rx>\\d+)/ print \"${iface} ${rx}\"\n  }\n}",
+        "  stats\n  rx 0\neth0:\n  rx 1\n  stats\n  rx 2\n  end\n  rx 3\n\nlo:\n  stats\n  rx 4\n",
+        "eth0 2\nlo 4\n"
+    );
+}
+
+#[test]
+fn range_after_unselected_guard() {
+    check_output!(
+        "/z/ { /a/,/b/ print 'never' }\n/b/,/d/ print _",
+        "a\nb\nc\nd\ne\n",
+        "b\nc\n"
+    );
+}
+
+#[test]
+fn range_under_negation() {
+    check_output!("!/b/,/d/ print _", "a\nb\nc\nd\ne\n", "a\nd\ne\n");
+}
+
+#[test]
+fn range_under_conjunction() {
+    check_output!("/b/,/e/ & /[cd]/ print _", "a\nb\nc\nd\ne\n", "c\nd\n");
+    check_output!("/[bd]/ & /a/,/c/ print _", "a\nb\nc\nd\n", "b\n");
+}
+
+#[test]
+fn range_under_disjunction() {
+    check_output!("/x/ | /b/,/d/ print _", "a\nb\nc\nx\nd\ne\n", "b\nc\nx\n");
+    check_output!("/^b/ | /b/,/d/ print _", "a\nb\nc\nd\n", "b\nc\n");
+}
+
+#[test]
+fn nested_range_exit_at_end() {
+    check_output!(
+        "/a/,/z/ {\n  /b/,/z/ {\n    exit { print 'closed' }\n  }\n}",
+        "a\nb\nc\n",
+        "closed\n"
+    );
+}
+
+#[test]
+fn inclusive_range() {
+    check_output!(
+        "/third/,=/fifth/ { print _ }",
+        "first\nsecond\nthird\nfourth\nfifth\nsexth\nseventh\n",
+        "third\nfourth\nfifth\n"
+    );
+    check_output!(
+        "/^(?P<a>a)/,=/^(?P<b>b)/ print \"${a}${b}\"",
+        "x\na\nc\nb\nb\n",
+        "a\na\nab\n"
+    );
+}
+
+#[test]
+fn range_hooks() {
+    check_output!(
+        "/^\\[(?P<name>\\w+)\\]/,/^$/ {\n  enter { print \"<${name}>\" }\n  exit { print \"</${name}>\" }\n  /=/ print _\n}",
+        "[a]\nx=1\n\n[b]\ny=2\n",
+        "<a>\nx=1\n</a>\n<b>\ny=2\n</b>\n"
+    );
+    check_output!(
+        "/^a/,=/^b/ {\n  exit { print 'done' }\n  print _\n}",
+        "a\nb\nc\n",
+        "a\nb\ndone\n"
+    );
+}
+
+#[test]
+fn inclusive_range_exit_on_last_line() {
+    check_output!(
+        "/a/,=/b/ { exit { print \"${_} ${NR}\" } }",
+        "a\nb\nc\n",
+        "b 2\n"
+    );
+    check_output!(
+        "/a/,/b/ { exit { print \"${_} ${NR}\" } }",
+        "a\nb\nc\n",
+        "b 2\n"
+    );
+}
+
+#[test]
+fn inclusive_range_exit_on_quit() {
+    check_output!(
+        "/a/,=/b/ {\n  exit { print \"closed ${_}\" }\n  /b/ quit\n  print _\n}",
+        "a\nb\nc\n",
+        "a\nclosed b\n"
+    );
+}
+
+#[test]
+fn last_line() {
+    check_output!("last append ';'", "a\nb\nc\n", "a\nb\nc;\n");
+    check_output!("last print _", "", "");
+}
+
+#[test]
+fn from_last_line() {
+    check_output!(
+        "-2 subst /,$/, ''\n-1 subst /,$/, '.'",
+        "a,\nb,\nc,\n",
+        "a,\nb\nc.\n"
+    );
+}
+
+#[test]
+fn last_line_pull() {
+    check_output!("/a/ pull\nlast append '!'", "a\nb\n", "a\nb!\n");
+}
+
+#[test]
+fn builtin_variables() {
+    check_output!(
+        "print \"${NR} ${FNR} ${NF} ${OFFSET}\"",
+        "a b\n\nc\n",
+        "1 1 2 0\n2 2 0 4\n3 3 1 5\n"
+    );
+    check_output!("NF >= 2 print NR", "a b\nc\nd e f\n", "1\n3\n");
+    check_output!("/a/ pull\nprint NR", "a\nb\nc\n", "2\n3\n");
+}
+
+#[test]
+fn builtin_filename() {
+    let interpreter = romulus::Interpreter::builder()
+        .expression("$ print \"${FILENAME} ${NR}\"".to_string())
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    interpreter.process_file("input.txt", &mut "a\nb\n".as_bytes(), &mut out);
+
+    assert_eq!(String::from_utf8(out).unwrap(), "input.txt 2\n");
+}
+
+#[test]
+fn numeric_comparison() {
+    check_output!(
+        "[_, path, code] & code >= 500 print path",
+        "GET /a 200\nGET /b 503\nGET /c 500\nGET /d 99\n",
+        "/b\n/c\n"
+    );
+}
+
+#[test]
+fn numeric_comparison_with_text() {
+    check_output!(
+        "[code] & code >= 500 print code\n[code] & code < 500 print \"low ${code}\"",
+        "503\nabc\n200\n",
+        "503\nlow 200\n"
+    );
+    check_output!(
+        "[code] & code != 500 print code",
+        "500\nabc\n500.0\n",
+        "abc\n"
+    );
+}
+
+#[test]
+fn string_comparison() {
+    check_output!(
+        "[user, _] & (user == 'root' | user < 'b') print _",
+        "root 1\nalice 2\nbob 3\n",
+        "root 1\nalice 2\n"
+    );
+}
+
+#[test]
+fn regex_comparison() {
+    check_output!(
+        "[path] & path !~ /\\.git/ & path ~ /\\.(?P<ext>[a-z]+)$/ print ext",
+        "src/main.rs\n.git/config\nREADME.md\nMakefile\n",
+        "rs\nmd\n"
+    );
+}
+
+#[test]
+fn emit_json() {
+    check_output!(
+        "[user, msg] emit json",
+        "alice say\"hi\"\nbob a\\b\n",
+        "{\"msg\":\"say\\\"hi\\\"\",\"user\":\"alice\"}\n{\"msg\":\"a\\\\b\",\"user\":\"bob\"}\n"
+    );
+}
+
+#[test]
+fn emit_csv() {
+    check_output!(
+        "/(?P<user>[a-z]+) (?P<msg>.*)/ emit csv(user, msg, NR)",
+        "alice hi, there\nbob say \"hi\"\n",
+        "user,msg,NR\nalice,\"hi, there\",1\nbob,\"say \"\"hi\"\"\",2\n"
+    );
+}
+
+#[test]
+fn emit_csv_columns() {
+    check_output!(
+        "/^a/ emit csv(NR)\n/^b/ emit csv(NR, _)\n/^c/ emit csv(NR)",
+        "a\nb\nc\nb\n",
+        "NR\n1\nNR,_\n2,b\n3\n4,b\n"
+    );
+}
+
+#[test]
+fn emit_logfmt() {
+    check_output!(
+        "/(?P<user>[a-z]+) (?P<msg>.*)/ { let empty = ''; emit logfmt(user, msg, empty) }",
+        "alice hi there\nbob k=v\n",
+        "user=alice msg=\"hi there\" empty=\"\"\nuser=bob msg=\"k=v\" empty=\"\"\n"
+    );
+}
+
+#[test]
+fn logfmt_pattern() {
+    check_output!(
+        "{level: 'error', msg, took: /(?P<ms>[0-9]+)ms/} print \"${msg} (${ms})\"",
+        "level=info msg=started took=1ms\nlevel=error msg=\"disk \\\"sda\\\" full\" took=35ms\nlevel=error msg=x\n",
+        "disk \"sda\" full (35)\n"
+    );
+}
+
+#[test]
+fn logfmt_quoted_keys() {
+    check_output!(
+        "{\"trace-id\": id, user: 'root'} print id\n!{user} print 'anonymous'",
+        "trace-id=ab12 user=root\nuser=bob trace-id=cd34\ntrace-id=ef56\n",
+        "ab12\nanonymous\n"
+    );
+}
+
+#[test]
+fn column_pattern() {
+    check_output!(
+        "[0..8 user, 8..16 pid, 17.. cmd] & pid > 10 print \"${user}|${pid}|${cmd}\"",
+        "root           1 /sbin/init splash\nalice      12345 vim notes.txt\n",
+        "alice|12345|vim notes.txt\n"
+    );
+}
+
+#[test]
+fn column_pattern_literal() {
+    check_output!(
+        "[0..8 'root', 17.. /(?P<prog>[^ ]+)/] print prog",
+        "root           1 /sbin/init splash\nalice      12345 vim notes.txt\n",
+        "/sbin/init\n"
+    );
+}
@@ -0,0 +1,143 @@
+extern crate romulus;
+
+use romulus::{Delimited, Input, Interpreter};
+
+fn run(prog: &str, delimited: Delimited, header: bool, input: &str) -> String {
+    let interpreter = Interpreter::builder()
+        .expression(prog.to_string())
+        .delimited(delimited)
+        .header(header)
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    interpreter.process(&mut input.as_bytes(), &mut out);
+
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn quoted_fields() {
+    assert_eq!(
+        run(
+            "[name, comment] print \"${name}: ${comment} (${NF})\"",
+            Delimited::Csv,
+            false,
+            "alice,\"likes \"\"quotes\"\", commas\"\nbob,plain\n"
+        ),
+        "alice: likes \"quotes\", commas (2)\nbob: plain (2)\n"
+    );
+}
+
+#[test]
+fn embedded_newlines() {
+    assert_eq!(
+        run(
+            "[_, note] print \"${NR}: ${note}\"",
+            Delimited::Csv,
+            false,
+            "a,\"one\ntwo\"\nb,three\n"
+        ),
+        "1: one\ntwo\n2: three\n"
+    );
+}
+
+#[test]
+fn stray_quote() {
+    assert_eq!(
+        run(
+            "[item, size] print \"${NR}: ${item} ${size}\"",
+            Delimited::Csv,
+            false,
+            "tv,55\" screen\nradio,small\n"
+        ),
+        "1: tv 55\" screen\n2: radio small\n"
+    );
+}
+
+#[test]
+fn tsv() {
+    assert_eq!(
+        run(
+            "[_, b] print b",
+            Delimited::Tsv,
+            false,
+            "a\tb,c\n\"x\ty\"\tz\n"
+        ),
+        "b,c\nz\n"
+    );
+}
+
+#[test]
+fn header_columns() {
+    assert_eq!(
+        run(
+            "age > 30 delete",
+            Delimited::Csv,
+            true,
+            "name,age\nalice,31\nbob,25\n"
+        ),
+        "name,age\nbob,25\n"
+    );
+    assert_eq!(
+        run(
+            "print \"${NR} ${name}\"",
+            Delimited::Csv,
+            true,
+            "name,age\nalice,31\nbob,25\n"
+        ),
+        "1 alice\n2 bob\n"
+    );
+}
+
+#[test]
+fn header_per_input() {
+    let interpreter = Interpreter::builder()
+        .expression("print \"${FILENAME} ${id}\"".to_string())
+        .delimited(Delimited::Csv)
+        .header(true)
+        .build()
+        .unwrap();
+
+    let inputs = vec![
+        Input::new("a", "id,x\n1,2\n".as_bytes()),
+        Input::new("b", "x,id\n3,4\n".as_bytes()),
+    ];
+
+    let mut out = Vec::new();
+    interpreter.process_inputs(inputs, &mut out);
+
+    assert_eq!(String::from_utf8(out).unwrap(), "a 1\nb 4\n");
+}
+
+#[test]
+fn header_last_line() {
+    let interpreter = Interpreter::builder()
+        .expression("-2 print \"${FILENAME} ${id}\"".to_string())
+        .delimited(Delimited::Csv)
+        .header(true)
+        .build()
+        .unwrap();
+
+    let inputs = vec![
+        Input::new("a", "id\n1\n2\n".as_bytes()),
+        Input::new("b", "id\n3\n".as_bytes()),
+        Input::new("c", "id\n".as_bytes()),
+    ];
+
+    let mut out = Vec::new();
+    interpreter.process_inputs(inputs, &mut out);
+
+    assert_eq!(String::from_utf8(out).unwrap(), "a 2\n");
+}
+
+#[test]
+fn header_lint() {
+    let interpreter = Interpreter::builder()
+        .expression("print name".to_string())
+        .header(true)
+        .build()
+        .unwrap();
+
+    assert!(interpreter.lint().is_empty());
+}
@@ -0,0 +1,66 @@
+extern crate romulus;
+
+use romulus::{Delimited, Interpreter};
+
+fn run(prog: &str, input: &str) -> String {
+    let interpreter = Interpreter::builder()
+        .expression(prog.to_string())
+        .header(true)
+        .fixed_width(true)
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    interpreter.process(&mut input.as_bytes(), &mut out);
+
+    String::from_utf8(out).unwrap()
+}
+
+const PS: &str = "USER         PID COMMAND
+root           1 /sbin/init splash
+alice      12345 vim notes.txt
+";
+
+#[test]
+fn header_columns() {
+    assert_eq!(
+        run("PID > 100 print \"${USER}: ${COMMAND} (${NF})\"", PS),
+        "alice: vim notes.txt (3)\n"
+    );
+}
+
+#[test]
+fn positional_patterns() {
+    assert_eq!(
+        run("[user, _, cmd] print \"${user}|${cmd}\"", PS),
+        "root|/sbin/init splash\nalice|vim notes.txt\n"
+    );
+}
+
+#[test]
+fn wide_values() {
+    assert_eq!(
+        run(
+            "print \"${USER}|${PID}|${COMMAND}\"",
+            "USER         PID COMMAND\na_very_long_user 7 sh -c true\n"
+        ),
+        "a_very_long_user|7|sh -c true\n"
+    );
+}
+
+#[test]
+fn requires_header() {
+    assert!(Interpreter::builder()
+        .expression("print _".to_string())
+        .fixed_width(true)
+        .build()
+        .is_err());
+
+    assert!(Interpreter::builder()
+        .expression("print _".to_string())
+        .header(true)
+        .fixed_width(true)
+        .delimited(Delimited::Csv)
+        .build()
+        .is_err());
+}
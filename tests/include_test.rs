@@ -0,0 +1,69 @@
+extern crate romulus;
+
+use romulus::Interpreter;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/include");
+
+fn run(interpreter: Interpreter, input: &str) -> String {
+    let mut out = Vec::new();
+    let mut sin = input.as_bytes();
+
+    interpreter.process(&mut sin, &mut out);
+
+    String::from_utf8(out).unwrap()
+}
+
+fn file(name: &str) -> Result<Interpreter, String> {
+    Interpreter::builder()
+        .filename(format!("{}/{}", FIXTURES, name))
+        .build()
+}
+
+#[test]
+fn include_relative_to_file() {
+    let interpreter = file("main.rom").unwrap();
+
+    assert_eq!(run(interpreter, "a 1\nb 2\n"), "--\na: 1\nb: 2\n");
+}
+
+#[test]
+fn include_relative_to_cwd() {
+    let interpreter = Interpreter::builder()
+        .expression(
+            "include \"tests/fixtures/include/lib/common.rom\"\n[name, size] show(name, size)"
+                .to_string(),
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(run(interpreter, "a 1\n"), "a: 1\n");
+}
+
+#[test]
+fn include_cycle() {
+    let err = file("cycle/a.rom").err().unwrap();
+
+    assert!(err.contains("include cycle"), "{}", err);
+    assert!(err.contains("cycle/a.rom -> "), "{}", err);
+}
+
+#[test]
+fn include_error_names_file() {
+    let err = file("bad/main.rom").err().unwrap();
+    assert!(err.contains("broken.rom: "), "{}", err);
+
+    let err = file("bad/missing.rom").err().unwrap();
+    assert!(err.contains("missing.rom: "), "{}", err);
+    assert!(err.contains("nowhere.rom"), "{}", err);
+}
+
+#[test]
+fn include_only_at_top_level() {
+    let err = Interpreter::builder()
+        .expression("/a/ { include \"lib.rom\" }".to_string())
+        .build()
+        .err()
+        .unwrap();
+
+    assert!(err.contains("top level"), "{}", err);
+}
@@ -0,0 +1,90 @@
+extern crate romulus;
+
+use romulus::{Input, Interpreter};
+
+fn run(prog: &str, inputs: &[(&str, &str)]) -> String {
+    let interpreter = Interpreter::builder()
+        .expression(prog.to_string())
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    let inputs = inputs
+        .iter()
+        .map(|(name, content)| Input::new(name, content.as_bytes()));
+
+    interpreter.process_inputs(inputs, &mut out);
+
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn one_run() {
+    assert_eq!(
+        run(
+            "^ print 'begin'\nprint \"${NR} ${FNR} ${_}\"\n$ print 'end'",
+            &[("a", "x\ny\n"), ("b", "z\n")]
+        ),
+        "begin\n1 1 x\n2 2 y\n3 1 z\nend\n"
+    );
+}
+
+#[test]
+fn file_events() {
+    assert_eq!(
+        run(
+            "beginfile print \"<${FILENAME}>\"\nprint _\nendfile print \"</${FILENAME} ${FNR}>\"",
+            &[("a", "x\ny\n"), ("b", ""), ("c", "z\n")]
+        ),
+        "<a>\nx\ny\n</a 2>\n<b>\n</b 0>\n<c>\nz\n</c 1>\n"
+    );
+}
+
+#[test]
+fn globals_carry_over() {
+    assert_eq!(
+        run(
+            "^ seen = ''\n[name] seen = \"${seen}${name}\"\n$ print seen",
+            &[("a", "x\n"), ("b", "y\n")]
+        ),
+        "xy\n"
+    );
+}
+
+#[test]
+fn last_line_of_run() {
+    assert_eq!(run("last print _", &[("a", "x\ny\n"), ("b", "z\n")]), "z\n");
+}
+
+#[test]
+fn last_line_before_empty_input() {
+    assert_eq!(
+        run(
+            "last print _\n-2 print \"second to last ${_}\"",
+            &[("a", "x\ny\n"), ("b", "z\n"), ("c", "")]
+        ),
+        "second to last y\nz\n"
+    );
+}
+
+#[test]
+fn lookahead_across_inputs() {
+    assert_eq!(
+        run(
+            "-3 print _",
+            &[("a", "w\nx\n"), ("b", ""), ("c", "y\n"), ("d", "z\n")]
+        ),
+        "x\n"
+    );
+}
+
+#[test]
+fn quit_ends_run() {
+    assert_eq!(
+        run(
+            "/y/ quit\nprint _\n$ print 'end'",
+            &[("a", "x\ny\n"), ("b", "z\n")]
+        ),
+        "x\n"
+    );
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "json")]
+
+extern crate romulus;
+
+use romulus::{Delimited, Interpreter, InvalidJson};
+
+fn run(prog: &str, invalid: InvalidJson, input: &str) -> String {
+    let interpreter = Interpreter::builder()
+        .expression(prog.to_string())
+        .json(invalid)
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    interpreter.process(&mut input.as_bytes(), &mut out);
+
+    String::from_utf8(out).unwrap()
+}
+
+const LOGS: &str = r#"{"level":"info","msg":"started","http":{"status":200}}
+{"level":"error","msg":"failed","http":{"status":503}}
+not json
+{"level":"error","msg":"missing","http":{"status":404}}
+"#;
+
+#[test]
+fn comparisons() {
+    assert_eq!(
+        run(
+            ".level == 'error' & .http.status >= 500 print .msg",
+            InvalidJson::Skip,
+            LOGS
+        ),
+        "failed\n"
+    );
+}
+
+#[test]
+fn interpolation() {
+    assert_eq!(
+        run(
+            ".http.status ~ /^4/ print \"${NR} ${.level}: ${.msg} ${.http}\"",
+            InvalidJson::Skip,
+            LOGS
+        ),
+        "4 error: missing {\"status\":404}\n"
+    );
+}
+
+#[test]
+fn binding() {
+    assert_eq!(
+        run(
+            ".level == 'error' { let code = .http.status + 1; print code }",
+            InvalidJson::Skip,
+            LOGS
+        ),
+        "504\n405\n"
+    );
+}
+
+#[test]
+fn missing_paths() {
+    assert_eq!(
+        run("print \"[${.nope.deeper}]\"", InvalidJson::Skip, LOGS),
+        "[]\n[]\n[]\n"
+    );
+}
+
+#[test]
+fn pass_invalid() {
+    assert_eq!(
+        run(".level == 'error' print .msg", InvalidJson::Pass, LOGS),
+        "failed\nnot json\nmissing\n"
+    );
+}
+
+#[test]
+fn implicit_print() {
+    assert_eq!(
+        run(".level == 'info' delete", InvalidJson::Skip, LOGS),
+        "{\"level\":\"error\",\"msg\":\"failed\",\"http\":{\"status\":503}}\n\
+         {\"level\":\"error\",\"msg\":\"missing\",\"http\":{\"status\":404}}\n"
+    );
+}
+
+#[test]
+fn error_stops() {
+    let interpreter = Interpreter::builder()
+        .expression(".level == 'error' print .msg $ print 'done'".to_string())
+        .json(InvalidJson::Error)
+        .build()
+        .unwrap();
+
+    let mut out = Vec::new();
+    let result = interpreter.try_process(&mut LOGS.as_bytes(), &mut out);
+
+    assert_eq!(String::from_utf8(out).unwrap(), "failed\n");
+    assert_eq!(
+        result.map_err(|err| err.starts_with("line 3: invalid JSON: ")),
+        Err(true)
+    );
+}
+
+#[test]
+fn not_objects() {
+    let lines = "3\n\"x\"\n[1]\n{\"a\":1}\n";
+
+    assert_eq!(run("print .a", InvalidJson::Skip, lines), "1\n");
+    assert_eq!(
+        run("print .a", InvalidJson::Pass, lines),
+        "3\n\"x\"\n[1]\n1\n"
+    );
+
+    let interpreter = Interpreter::builder()
+        .expression("print .a".to_string())
+        .json(InvalidJson::Error)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        interpreter.try_process(&mut lines.as_bytes(), &mut Vec::new()),
+        Err("line 1: invalid JSON: expected a JSON object".to_string())
+    );
+}
+
+#[test]
+fn no_delimited_json() {
+    assert!(Interpreter::builder()
+        .expression("print .a".to_string())
+        .json(InvalidJson::Skip)
+        .delimited(Delimited::Csv)
+        .build()
+        .is_err());
+}
@@ -0,0 +1,97 @@
+extern crate romulus;
+
+fn lint(prog: &str) -> usize {
+    romulus::Interpreter::builder()
+        .expression(prog.to_string())
+        .build()
+        .unwrap()
+        .lint()
+        .len()
+}
+
+#[test]
+fn undefined_variable() {
+    assert_eq!(lint("print missing"), 1);
+}
+
+#[test]
+fn let_provides_for_block() {
+    assert_eq!(lint("/(?P<a>.)/ { let b = a; print b }"), 0);
+    assert_eq!(lint("/(?P<a>.)/ { let b = a }\nprint b"), 1);
+}
+
+#[test]
+fn assignment_provides_globally() {
+    assert_eq!(lint("$ print last\n/(?P<a>.)/ last = a"), 0);
+}
+
+#[test]
+fn for_provides_key() {
+    assert_eq!(
+        lint("[ip] incr count[ip]\n$ { for ip in count { print count[ip] } }"),
+        0
+    );
+    assert_eq!(lint("$ { for ip in count { print ip } }"), 1);
+}
+
+#[test]
+fn unknown_function() {
+    assert_eq!(lint("print upper(_)"), 0);
+    assert_eq!(lint("print shout(_)"), 1);
+}
+
+#[test]
+fn function_arity() {
+    assert_eq!(lint("print substr(_, 1, 2)"), 0);
+    assert_eq!(lint("print substr(_)"), 1);
+    assert_eq!(lint("print upper(_, _)"), 1);
+}
+
+#[test]
+fn comparison_sees_conjunction() {
+    assert_eq!(lint("[_, code] & code >= 500 print _"), 0);
+    assert_eq!(lint("code >= 500 print _"), 1);
+    assert_eq!(lint("[p] & p ~ /(?P<ext>[a-z]+)/ print ext"), 0);
+}
+
+#[test]
+fn switch_arm_scope() {
+    assert_eq!(lint("match { [a] => print a, _ => print _ }"), 0);
+    assert_eq!(lint("match { [a] => print a, _ => print a }"), 1);
+}
+
+#[test]
+fn procedure_parameters() {
+    assert_eq!(lint("def f(a) { print a }\n[x] f(x)"), 0);
+    assert_eq!(lint("def f(a) { print x }\n[x] f(x)"), 1);
+    assert_eq!(lint("let x = 1\ndef f() { print x }"), 1);
+}
+
+#[test]
+fn procedure_calls() {
+    assert_eq!(lint("g(_)"), 1);
+    assert_eq!(lint("def f(a) { print a }\nf(_, _)"), 1);
+}
+
+#[test]
+fn range_hook_scope() {
+    assert_eq!(lint("/(?P<a>a)/,/b/ { enter { print a } }"), 0);
+    assert_eq!(lint("/(?P<a>a)/,/b/ { exit { print c } }"), 1);
+    assert_eq!(lint("/a/,=/(?P<b>b)/ print b"), 0);
+}
+
+#[test]
+fn builtin_variables() {
+    assert_eq!(lint("print \"${FILENAME}:${FNR}\""), 0);
+    assert_eq!(lint("NF > 2 print NR"), 0);
+}
+
+#[test]
+fn logfmt_provides_keys() {
+    assert_eq!(lint("{level: 'error', msg} print msg"), 0);
+    assert_eq!(
+        lint("{took: /(?P<ms>[0-9]+)/, msg: m} print \"${m} ${ms}\""),
+        0
+    );
+    assert_eq!(lint("{level: 'error'} print level"), 1);
+}